@@ -8,5 +8,6 @@ fn main() {
     write_primes_program(25, "primes25");
     write_primes_program(TOP_ROOT_OF_U32, "primes");
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/lang.lalrpop");
     lalrpop::process_root().unwrap();
 }
@@ -0,0 +1,378 @@
+// Arena-backed store of expressions for large inputs.
+//
+// Nodes live in a single vector and refer to their children by
+// NodeId. Nodes are hash-consed: inserting a node that is already in
+// the arena returns the existing id, so identical subtrees are stored
+// only once. Because children are always inserted before their parent,
+// a child has a smaller id than its parent.
+//
+// All traversals use an explicit stack instead of recursion, so long
+// generated chains like 1 + 1 + ... + 1 do not overflow the call stack.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use crate::expression::Expression;
+use crate::expression::Operator;
+use crate::visit::Fold;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Node {
+    Number(String),
+    Variable(String),
+    Negation(NodeId),
+    Composite(Operator, NodeId, NodeId),
+    Call(String, Vec<NodeId>)
+}
+
+#[derive(Default)]
+pub struct ExpressionArena {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, NodeId>
+}
+
+// Work items of the iterative traversals. A node is entered before its
+// children are handled and exited after them.
+enum Step<T> {
+    Enter(T),
+    Exit(T)
+}
+
+impl ExpressionArena {
+    pub fn new() -> ExpressionArena {
+        ExpressionArena{nodes: Vec::new(), ids: HashMap::new()}
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+
+    pub fn intern(&mut self, node: Node) -> NodeId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    pub fn number(&mut self, number: &str) -> NodeId {
+        self.intern(Node::Number(number.to_owned()))
+    }
+
+    pub fn variable(&mut self, name: &str) -> NodeId {
+        self.intern(Node::Variable(name.to_owned()))
+    }
+
+    pub fn negation(&mut self, inner: NodeId) -> NodeId {
+        self.intern(Node::Negation(inner))
+    }
+
+    pub fn composite(&mut self, left: NodeId, operator: Operator, right: NodeId) -> NodeId {
+        self.intern(Node::Composite(operator, left, right))
+    }
+
+    pub fn call(&mut self, name: &str, arguments: Vec<NodeId>) -> NodeId {
+        self.intern(Node::Call(name.to_owned(), arguments))
+    }
+
+    pub fn insert(&mut self, e: &Expression) -> NodeId {
+        let mut steps: Vec<Step<&Expression>> = vec![Step::Enter(e)];
+        let mut results: Vec<NodeId> = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Enter(e) => match e {
+                    Expression::Number(n) => results.push(self.number(n)),
+                    Expression::Variable(v) => results.push(self.variable(v)),
+                    Expression::Negation(inner) => {
+                        steps.push(Step::Exit(e));
+                        steps.push(Step::Enter(inner));
+                    },
+                    Expression::Composite(c) => {
+                        steps.push(Step::Exit(e));
+                        steps.push(Step::Enter(&c.right));
+                        steps.push(Step::Enter(&c.left));
+                    },
+                    Expression::Call(c) => {
+                        steps.push(Step::Exit(e));
+                        steps.extend(c.arguments.iter().rev().map(Step::Enter));
+                    }
+                },
+                Step::Exit(e) => {
+                    let id = match e {
+                        Expression::Negation(_) => {
+                            let inner = results.pop().unwrap();
+                            self.negation(inner)
+                        },
+                        Expression::Composite(c) => {
+                            let right = results.pop().unwrap();
+                            let left = results.pop().unwrap();
+                            self.composite(left, c.operator.clone(), right)
+                        },
+                        Expression::Call(c) => {
+                            let arguments = results.split_off(results.len() - c.arguments.len());
+                            self.call(&c.name, arguments)
+                        },
+                        _ => unreachable!()
+                    };
+                    results.push(id);
+                }
+            }
+        }
+        results.pop().unwrap()
+    }
+
+    // Every node reachable from root exactly once, children before parents.
+    pub fn post_order(&self, root: NodeId) -> Vec<NodeId> {
+        let mut result: Vec<NodeId> = Vec::new();
+        let mut seen: HashSet<NodeId> = HashSet::new();
+        let mut steps: Vec<Step<NodeId>> = vec![Step::Enter(root)];
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Enter(id) => {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    steps.push(Step::Exit(id));
+                    match self.node(id) {
+                        Node::Negation(inner) => steps.push(Step::Enter(*inner)),
+                        Node::Composite(_, left, right) => {
+                            steps.push(Step::Enter(*right));
+                            steps.push(Step::Enter(*left));
+                        },
+                        Node::Call(_, arguments) => steps.extend(arguments.iter().rev().map(|a| Step::Enter(*a))),
+                        _ => ()
+                    }
+                },
+                Step::Exit(id) => result.push(id)
+            }
+        }
+        result
+    }
+
+    // Fold the tree below root. A shared subtree is folded only once
+    // and its value is cloned for every parent that uses it.
+    pub fn fold<F: Fold>(&self, root: NodeId, folder: &mut F) -> F::Output where F::Output: Clone {
+        let mut values: HashMap<NodeId, F::Output> = HashMap::new();
+        for id in self.post_order(root) {
+            let value = match self.node(id) {
+                Node::Number(n) => folder.fold_number(n),
+                Node::Variable(v) => folder.fold_variable(v),
+                Node::Negation(inner) => folder.fold_negation(values[inner].clone()),
+                Node::Composite(operator, left, right) => {
+                    folder.fold_composite(operator, values[left].clone(), values[right].clone())
+                },
+                Node::Call(name, arguments) => {
+                    let arguments = arguments.iter().map(|a| values[a].clone()).collect();
+                    folder.fold_call(name, arguments)
+                }
+            };
+            values.insert(id, value);
+        }
+        values.remove(&root).unwrap()
+    }
+
+    // The boxed tree below root, with shared subtrees copied. It is
+    // built without recursion, but dropping, cloning or comparing an
+    // Expression recurses, so this is only for trees of moderate depth.
+    // Keep long generated chains in the arena.
+    pub fn to_expression(&self, root: NodeId) -> Expression {
+        let mut steps: Vec<Step<NodeId>> = vec![Step::Enter(root)];
+        let mut results: Vec<Expression> = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Enter(id) => match self.node(id) {
+                    Node::Number(n) => results.push(Expression::Number(n.clone())),
+                    Node::Variable(v) => results.push(Expression::Variable(v.clone())),
+                    Node::Negation(inner) => {
+                        steps.push(Step::Exit(id));
+                        steps.push(Step::Enter(*inner));
+                    },
+                    Node::Composite(_, left, right) => {
+                        steps.push(Step::Exit(id));
+                        steps.push(Step::Enter(*right));
+                        steps.push(Step::Enter(*left));
+                    },
+                    Node::Call(_, arguments) => {
+                        steps.push(Step::Exit(id));
+                        steps.extend(arguments.iter().rev().map(|a| Step::Enter(*a)));
+                    }
+                },
+                Step::Exit(id) => {
+                    let e = match self.node(id) {
+                        Node::Negation(_) => Expression::negation(results.pop().unwrap()),
+                        Node::Composite(operator, _, _) => {
+                            let right = results.pop().unwrap();
+                            let left = results.pop().unwrap();
+                            Expression::composite(left, operator.clone(), right)
+                        },
+                        Node::Call(name, arguments) => {
+                            let arguments = results.split_off(results.len() - arguments.len());
+                            Expression::call(name, arguments)
+                        },
+                        _ => unreachable!()
+                    };
+                    results.push(e);
+                }
+            }
+        }
+        results.pop().unwrap()
+    }
+
+    // The same text as Expression::to_string, written piece by piece.
+    pub fn to_string(&self, root: NodeId) -> String {
+        enum Piece<'a> {
+            Node(NodeId),
+            Text(&'a str)
+        }
+        let mut result = String::new();
+        let mut pieces: Vec<Piece> = vec![Piece::Node(root)];
+        while let Some(piece) = pieces.pop() {
+            match piece {
+                Piece::Text(text) => result.push_str(text),
+                Piece::Node(id) => match self.node(id) {
+                    Node::Number(n) => result.push_str(n),
+                    Node::Variable(v) => result.push_str(v),
+                    Node::Negation(inner) => {
+                        result.push_str("(-");
+                        pieces.push(Piece::Text(")"));
+                        pieces.push(Piece::Node(*inner));
+                    },
+                    Node::Composite(operator, left, right) => {
+                        result.push('(');
+                        pieces.push(Piece::Text(")"));
+                        pieces.push(Piece::Node(*right));
+                        pieces.push(Piece::Text(operator_text(operator)));
+                        pieces.push(Piece::Node(*left));
+                    },
+                    Node::Call(name, arguments) => {
+                        result.push_str(name);
+                        result.push('(');
+                        pieces.push(Piece::Text(")"));
+                        for (i, argument) in arguments.iter().enumerate().rev() {
+                            pieces.push(Piece::Node(*argument));
+                            if i > 0 {
+                                pieces.push(Piece::Text(", "));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+fn operator_text(operator: &Operator) -> &'static str {
+    match operator {
+        Operator::PLUS => " + ",
+        Operator::MINUS => " - ",
+        Operator::MULT => " * ",
+        Operator::DIV => " / ",
+        Operator::POW => " ^ "
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use ::num_rational::Rational32;
+    use super::*;
+    use crate::lang::ExprParser;
+    use crate::evaluate::evaluate;
+    use crate::surd::Surd;
+
+    struct Evaluate;
+
+    impl Fold for Evaluate {
+        type Output = Option<Rational32>;
+
+        fn fold_number(&mut self, number: &str) -> Option<Rational32> {
+            number.parse::<i32>().ok().map(Rational32::from)
+        }
+
+        fn fold_variable(&mut self, _name: &str) -> Option<Rational32> {
+            Some(Rational32::from(2))
+        }
+
+        fn fold_negation(&mut self, inner: Option<Rational32>) -> Option<Rational32> {
+            Some(-inner?)
+        }
+
+        fn fold_composite(&mut self, operator: &Operator, left: Option<Rational32>, right: Option<Rational32>) -> Option<Rational32> {
+            crate::evaluate::apply(operator, left?, right?)
+        }
+
+        fn fold_call(&mut self, name: &str, arguments: Vec<Option<Rational32>>) -> Option<Rational32> {
+            let arguments = arguments.into_iter().map(|a| a.map(Surd::from_rational)).collect::<Option<Vec<_>>>()?;
+            crate::evaluate::apply_function(name, &arguments)?.to_rational()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let e = ExprParser::new().parse("-(x + 1)^2 * y / 3 - f(x, sqrt(y), 1)").unwrap();
+        let mut arena = ExpressionArena::new();
+        let id = arena.insert(&e);
+        assert_eq!(arena.to_string(id), e.to_string());
+        assert_eq!(arena.to_expression(id), *e);
+    }
+
+    #[test]
+    fn identical_subtrees_are_shared() {
+        let e = ExprParser::new().parse("(x + 1) * (x + 1) + (x + 1)").unwrap();
+        let mut arena = ExpressionArena::new();
+        let id = arena.insert(&e);
+        // x, 1, x + 1, (x + 1) * (x + 1) and the sum
+        assert_eq!(arena.len(), 5);
+        assert_eq!(arena.post_order(id).len(), 5);
+        assert_eq!(arena.insert(&e), id);
+    }
+
+    #[test]
+    fn fold_matches_evaluate() {
+        let e = ExprParser::new().parse("(x + 1) * (x + 1) - x^3 / 4 + sqrt(x*8)").unwrap();
+        let mut arena = ExpressionArena::new();
+        let id = arena.insert(&e);
+        let mut values = HashMap::new();
+        values.insert(String::from("x"), Rational32::from(2));
+        assert_eq!(arena.fold(id, &mut Evaluate), evaluate(&e, &values));
+    }
+
+    #[test]
+    fn long_chain_does_not_overflow_stack() {
+        let mut arena = ExpressionArena::new();
+        let one = arena.number("1");
+        let x = arena.variable("x");
+        let mut sum = x;
+        for _ in 0 .. 100000 {
+            sum = arena.composite(sum, Operator::PLUS, one);
+        }
+        assert_eq!(arena.len(), 100002);
+        let text = arena.to_string(sum);
+        assert!(text[99998 ..].starts_with("((x + 1) + 1) + 1)"));
+        assert_eq!(text.len(), 1 + 100000 * 6);
+        assert_eq!(arena.fold(sum, &mut Evaluate), Some(Rational32::from(100002)));
+    }
+
+    #[test]
+    fn insert_long_parsed_chain() {
+        let input = vec!["x"; 5000].join(" + ");
+        let e = ExprParser::new().parse(&input).unwrap();
+        let mut arena = ExpressionArena::new();
+        let id = arena.insert(&e);
+        assert_eq!(arena.len(), 5000);
+        assert_eq!(arena.to_string(id), e.to_string());
+    }
+}
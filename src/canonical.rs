@@ -0,0 +1,141 @@
+// Canonical form of expressions modulo commutativity and
+// associativity of + and *.
+//
+// Chains of + and of * are flattened, their operands are sorted by the
+// derived ordering of Expression and the chain is rebuilt
+// left-associatively. Two expressions that only differ in the order or
+// grouping of their terms and factors, like x*y + 1 and 1 + y*x, get
+// the same canonical form. Nothing else is rewritten: - and / are
+// neither commutative nor associative, and x + x stays different from
+// 2*x. Use simplify::simplify for that.
+
+use crate::expression::Expression;
+use crate::expression::Equation;
+use crate::expression::Operator;
+use crate::visit::Fold;
+use crate::visit::fold;
+
+pub fn canonical(e: &Expression) -> Expression {
+    fold(e, &mut Canonicalizer)
+}
+
+pub fn equivalent(first: &Expression, second: &Expression) -> bool {
+    canonical(first) == canonical(second)
+}
+
+// Both sides in canonical form, with the smaller side on the left,
+// because a = b and b = a describe the same equation.
+pub fn canonical_equation(e: &Equation) -> Equation {
+    let left = canonical(&e.left);
+    let right = canonical(&e.right);
+    if right < left {
+        Equation::new(Box::new(right), Box::new(left))
+    } else {
+        Equation::new(Box::new(left), Box::new(right))
+    }
+}
+
+pub fn equivalent_equations(first: &Equation, second: &Equation) -> bool {
+    canonical_equation(first) == canonical_equation(second)
+}
+
+struct Canonicalizer;
+
+impl Fold for Canonicalizer {
+    type Output = Expression;
+
+    fn fold_number(&mut self, number: &str) -> Expression {
+        Expression::Number(number.to_owned())
+    }
+
+    fn fold_variable(&mut self, name: &str) -> Expression {
+        Expression::Variable(name.to_owned())
+    }
+
+    fn fold_negation(&mut self, inner: Expression) -> Expression {
+        Expression::negation(inner)
+    }
+
+    // The children are already canonical, so an operand that is a chain
+    // of the same operator is already sorted and only has to be merged.
+    fn fold_composite(&mut self, operator: &Operator, left: Expression, right: Expression) -> Expression {
+        if !matches!(operator, Operator::PLUS | Operator::MULT) {
+            return Expression::composite(left, operator.clone(), right);
+        }
+        let mut operands: Vec<Expression> = Vec::new();
+        flatten(left, operator, &mut operands);
+        flatten(right, operator, &mut operands);
+        operands.sort();
+        let mut operands = operands.into_iter();
+        let first = operands.next().unwrap();
+        operands.fold(first, |chain, operand| Expression::composite(chain, operator.clone(), operand))
+    }
+
+    fn fold_call(&mut self, name: &str, arguments: Vec<Expression>) -> Expression {
+        Expression::call(name, arguments)
+    }
+}
+
+fn flatten(e: Expression, operator: &Operator, operands: &mut Vec<Expression>) {
+    match e {
+        Expression::Composite(c) if c.operator == *operator => {
+            flatten(*c.left, operator, operands);
+            flatten(*c.right, operator, operands);
+        },
+        other => operands.push(other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use super::*;
+    use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
+
+    fn parse(input: &str) -> Expression {
+        *ExprParser::new().parse(input).unwrap()
+    }
+
+    #[test]
+    fn commutative_and_associative() {
+        assert!(equivalent(&parse("x*y + 1"), &parse("1 + y*x")));
+        assert!(equivalent(&parse("(a + b) + c"), &parse("a + (c + b)")));
+        assert!(equivalent(&parse("2*(x*y)*z"), &parse("z*(y*2)*x")));
+        assert!(equivalent(&parse("(x + 1)^(y*2)"), &parse("(1 + x)^(2*y)")));
+        assert!(equivalent(&parse("sqrt(x*y + 1)"), &parse("sqrt(1 + y*x)")));
+        assert!(!equivalent(&parse("f(x, y)"), &parse("f(y, x)")));
+    }
+
+    #[test]
+    fn not_commutative() {
+        assert!(!equivalent(&parse("x - y"), &parse("y - x")));
+        assert!(!equivalent(&parse("x / y"), &parse("y / x")));
+        assert!(!equivalent(&parse("x^y"), &parse("y^x")));
+        assert!(!equivalent(&parse("x + x"), &parse("2*x")));
+        assert!(!equivalent(&parse("(a + b) * c"), &parse("a + b * c")));
+    }
+
+    #[test]
+    fn canonical_is_idempotent() {
+        let c = canonical(&parse("y*x + 3 + -(b*a) + x^2"));
+        assert_eq!(c.to_string(), "(((3 + (-(a * b))) + (x * y)) + (x ^ 2))");
+        assert_eq!(canonical(&c), c);
+    }
+
+    #[test]
+    fn dedupe_with_hash_set() {
+        let inputs = ["x*y + 1", "1 + y*x", "x + y", "y + x", "x - y"];
+        let distinct: HashSet<Expression> = inputs.iter().map(|input| canonical(&parse(input))).collect();
+        assert_eq!(distinct.len(), 3);
+    }
+
+    #[test]
+    fn equations() {
+        let first = EquationParser::new().parse("x^2 + y^2 = 25").unwrap();
+        let second = EquationParser::new().parse("25 = y^2 + x^2").unwrap();
+        let third = EquationParser::new().parse("x^2 + y^2 = 16").unwrap();
+        assert!(equivalent_equations(&first, &second));
+        assert!(!equivalent_equations(&first, &third));
+    }
+}
@@ -0,0 +1,322 @@
+// Conics A x^2 + B xy + C y^2 + D x + E y + F = 0 with rational
+// coefficients.
+//
+// The quadratic coefficients may all be zero, so lines are conics as
+// well. A conic and any nonzero multiple of it describe the same
+// curve, but they are different values of this type.
+
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::One;
+use num_traits::Signed;
+use num_traits::Zero;
+use crate::expression::Equation;
+use crate::polynomial::Monomial;
+use crate::polynomial::Polynomial;
+
+// What the points of a conic look like. Circles are ellipses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConicKind {
+    Ellipse,
+    Hyperbola,
+    Parabola,
+    // x^2 + y^2 = 0
+    Point,
+    // x^2 - y^2 = 0
+    IntersectingLines,
+    // x^2 = 1
+    ParallelLines,
+    // x^2 = 0
+    CoincidentLines,
+    // Quadratic terms vanish: D x + E y + F = 0 with D or E nonzero.
+    Line,
+    // 0 = 0
+    Plane,
+    // No real points, like x^2 + y^2 = -1, x^2 = -1 or 0 = 1.
+    Empty
+}
+
+// Rows of a 3x3 matrix.
+pub type Matrix3 = [[Rational32; 3]; 3];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Conic {
+    pub a: Rational32,
+    pub b: Rational32,
+    pub c: Rational32,
+    pub d: Rational32,
+    pub e: Rational32,
+    pub f: Rational32
+}
+
+impl Conic {
+    pub fn new(a: Rational32, b: Rational32, c: Rational32, d: Rational32, e: Rational32, f: Rational32) -> Conic {
+        Conic{a, b, c, d, e, f}
+    }
+
+    // None unless the polynomial has degree at most 2 and no variables
+    // other than x and y.
+    pub fn from_polynomial(p: &Polynomial) -> Option<Conic> {
+        if p.degree() > 2 || p.variables().iter().any(|v| v != "x" && v != "y") {
+            return None;
+        }
+        let coefficient = |x: u32, y: u32| p.coefficient(&monomial(x, y));
+        Some(Conic::new(
+            coefficient(2, 0),
+            coefficient(1, 1),
+            coefficient(0, 2),
+            coefficient(1, 0),
+            coefficient(0, 1),
+            coefficient(0, 0)))
+    }
+
+    // The conic (x, y, 1) M (x, y, 1)^T = 0 of a symmetric matrix M,
+    // None if M is not symmetric.
+    pub fn from_matrix(m: &Matrix3) -> Option<Conic> {
+        if m[0][1] != m[1][0] || m[0][2] != m[2][0] || m[1][2] != m[2][1] {
+            return None;
+        }
+        let two = Rational32::from(2);
+        Some(Conic::new(
+            m[0][0],
+            m[0][1].checked_mul(&two)?,
+            m[1][1],
+            m[0][2].checked_mul(&two)?,
+            m[1][2].checked_mul(&two)?,
+            m[2][2]))
+    }
+
+    // The conic residual = 0 of the equation left = right.
+    pub fn from_equation(e: &Equation) -> Option<Conic> {
+        Conic::from_polynomial(&Polynomial::from_expression(&e.residual())?)
+    }
+
+    pub fn is_circle(&self) -> bool {
+        self.a == self.c && self.b.is_zero() && self.kind() == Some(ConicKind::Ellipse)
+    }
+
+    // AC - B^2/4, the determinant of the quadratic part. Positive for
+    // ellipses, zero for parabolas and negative for hyperbolas.
+    pub fn discriminant(&self) -> Option<Rational32> {
+        let b_squared = self.b.checked_mul(&self.b)?;
+        self.a.checked_mul(&self.c)?.checked_sub(&b_squared.checked_div(&Rational32::from(4))?)
+    }
+
+    // The determinant of the symmetric matrix
+    //   A   B/2 D/2
+    //   B/2 C   E/2
+    //   D/2 E/2 F
+    // which is zero exactly for degenerate conics.
+    pub fn determinant(&self) -> Option<Rational32> {
+        let four = Rational32::from(4);
+        let acf = self.a.checked_mul(&self.c)?.checked_mul(&self.f)?;
+        let bde = self.b.checked_mul(&self.d)?.checked_mul(&self.e)?;
+        let ae2 = self.a.checked_mul(&self.e)?.checked_mul(&self.e)?;
+        let cd2 = self.c.checked_mul(&self.d)?.checked_mul(&self.d)?;
+        let fb2 = self.f.checked_mul(&self.b)?.checked_mul(&self.b)?;
+        let quarter = bde.checked_sub(&ae2)?.checked_sub(&cd2)?.checked_sub(&fb2)?.checked_div(&four)?;
+        acf.checked_add(&quarter)
+    }
+
+    // Classified by the invariants: the discriminant, the determinant,
+    // the trace A + C and, for parallel lines, the sum of the minors
+    // AF - D^2/4 + CF - E^2/4. None on overflow.
+    pub fn kind(&self) -> Option<ConicKind> {
+        if self.a.is_zero() && self.b.is_zero() && self.c.is_zero() {
+            return Some(if !self.d.is_zero() || !self.e.is_zero() {
+                ConicKind::Line
+            } else if self.f.is_zero() {
+                ConicKind::Plane
+            } else {
+                ConicKind::Empty
+            });
+        }
+        let discriminant = self.discriminant()?;
+        let determinant = self.determinant()?;
+        let kind = if discriminant.is_positive() {
+            let trace = self.a.checked_add(&self.c)?;
+            if determinant.is_zero() {
+                ConicKind::Point
+            } else if trace.checked_mul(&determinant)?.is_negative() {
+                ConicKind::Ellipse
+            } else {
+                ConicKind::Empty
+            }
+        } else if discriminant.is_negative() {
+            if determinant.is_zero() {
+                ConicKind::IntersectingLines
+            } else {
+                ConicKind::Hyperbola
+            }
+        } else if !determinant.is_zero() {
+            ConicKind::Parabola
+        } else {
+            let four = Rational32::from(4);
+            let first = self.a.checked_mul(&self.f)?.checked_sub(&self.d.checked_mul(&self.d)?.checked_div(&four)?)?;
+            let second = self.c.checked_mul(&self.f)?.checked_sub(&self.e.checked_mul(&self.e)?.checked_div(&four)?)?;
+            let minors = first.checked_add(&second)?;
+            if minors.is_negative() {
+                ConicKind::ParallelLines
+            } else if minors.is_zero() {
+                ConicKind::CoincidentLines
+            } else {
+                ConicKind::Empty
+            }
+        };
+        Some(kind)
+    }
+
+    // The symmetric matrix
+    //   A   B/2 D/2
+    //   B/2 C   E/2
+    //   D/2 E/2 F
+    // with A x^2 + B xy + C y^2 + D x + E y + F = (x, y, 1) M (x, y, 1)^T.
+    pub fn matrix(&self) -> Option<Matrix3> {
+        let two = Rational32::from(2);
+        let half_b = self.b.checked_div(&two)?;
+        let half_d = self.d.checked_div(&two)?;
+        let half_e = self.e.checked_div(&two)?;
+        Some([
+            [self.a, half_b, half_d],
+            [half_b, self.c, half_e],
+            [half_d, half_e, self.f]
+        ])
+    }
+
+    pub fn to_polynomial(self) -> Polynomial {
+        let x = Polynomial::variable("x");
+        let y = Polynomial::variable("y");
+        let terms = [
+            (self.a, x.checked_mul(&x)),
+            (self.b, x.checked_mul(&y)),
+            (self.c, y.checked_mul(&y)),
+            (self.d, Some(x)),
+            (self.e, Some(y)),
+            (self.f, Some(Polynomial::constant(Rational32::from(1))))
+        ];
+        let mut result = Polynomial::zero();
+        for (c, term) in terms.iter() {
+            if !c.is_zero() {
+                result = result.checked_add(&term.as_ref().unwrap().checked_scale(*c).unwrap()).unwrap();
+            }
+        }
+        result
+    }
+}
+
+fn monomial(x: u32, y: u32) -> Monomial {
+    let mut result = Monomial::new();
+    if x > 0 {
+        result.insert(String::from("x"), x);
+    }
+    if y > 0 {
+        result.insert(String::from("y"), y);
+    }
+    result
+}
+
+// Terms in the usual order x^2, xy, y^2, x, y, 1.
+impl ToString for Conic {
+    fn to_string(&self) -> String {
+        let terms = [(self.a, "x^2"), (self.b, "x*y"), (self.c, "y^2"), (self.d, "x"), (self.e, "y"), (self.f, "")];
+        let mut result = String::new();
+        for (c, name) in terms.iter() {
+            if c.is_zero() {
+                continue;
+            }
+            if c.is_negative() {
+                result.push_str(if result.is_empty() { "-" } else { " - " });
+            } else if !result.is_empty() {
+                result.push_str(" + ");
+            }
+            let magnitude = c.abs();
+            if name.is_empty() {
+                result.push_str(&magnitude.to_string());
+            } else if magnitude.is_one() {
+                result.push_str(name);
+            } else {
+                result.push_str(&format!("{}*{}", magnitude, name));
+            }
+        }
+        if result.is_empty() {
+            result.push('0');
+        }
+        result.push_str(" = 0");
+        result
+    }
+}
+
+// The conic of an equation, for the tests of modules built on conics.
+#[cfg(test)]
+pub fn parse_conic(input: &str) -> Conic {
+    Conic::from_equation(&crate::lang::EquationParser::new().parse(input).unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::EquationParser;
+
+    fn conic(input: &str) -> Option<Conic> {
+        Conic::from_equation(&EquationParser::new().parse(input).unwrap())
+    }
+
+    fn r(n: i32) -> Rational32 {
+        Rational32::from(n)
+    }
+
+    #[test]
+    fn coefficients() {
+        assert_eq!(conic("x^2 + x*y - 2*y^2 + 3*x = 4*y - 5"), Some(Conic::new(r(1), r(1), r(-2), r(3), r(-4), r(5))));
+        assert_eq!(conic("y = 2*x + 1"), Some(Conic::new(r(0), r(0), r(0), r(-2), r(1), r(-1))));
+        assert_eq!(conic("x^2/4 + y^2/9 = 1").unwrap().a, Rational32::new(1, 4));
+    }
+
+    #[test]
+    fn not_a_conic() {
+        assert_eq!(conic("x^3 = y"), None);
+        assert_eq!(conic("x^2*y = 1"), None);
+        assert_eq!(conic("x^2 + z^2 = 1"), None);
+        assert_eq!(conic("1/x = y"), None);
+    }
+
+    #[test]
+    fn kinds() {
+        let kind = |input: &str| conic(input).unwrap().kind().unwrap();
+        assert_eq!(kind("x^2/4 + y^2/9 = 1"), ConicKind::Ellipse);
+        assert_eq!(kind("5*x^2 + 4*x*y + 8*y^2 = 36"), ConicKind::Ellipse);
+        assert_eq!(kind("x*y = 1"), ConicKind::Hyperbola);
+        assert_eq!(kind("x^2 - 2*x*y + y^2 = x + y"), ConicKind::Parabola);
+        assert_eq!(kind("(x - 1)^2 + 2*(y + 3)^2 = 0"), ConicKind::Point);
+        assert_eq!(kind("x^2 + y^2 + 1 = 0"), ConicKind::Empty);
+        assert_eq!(kind("(x - y)*(x + 2*y - 1) = 0"), ConicKind::IntersectingLines);
+        assert_eq!(kind("(x + y)^2 = 4"), ConicKind::ParallelLines);
+        assert_eq!(kind("(x + y - 1)^2 = 0"), ConicKind::CoincidentLines);
+        assert_eq!(kind("(x + y)^2 = -4"), ConicKind::Empty);
+        assert_eq!(kind("y = 2*x"), ConicKind::Line);
+        assert_eq!(kind("x = x"), ConicKind::Plane);
+        assert_eq!(kind("x = x + 1"), ConicKind::Empty);
+        assert!(conic("(x - 1)^2 + (y - 2)^2 = 4").unwrap().is_circle());
+        assert!(!conic("x^2 + y^2 = -4").unwrap().is_circle());
+    }
+
+    #[test]
+    fn matrix() {
+        let c = conic("x^2 + x*y - 2*y^2 + 3*x = 4*y - 5").unwrap();
+        let m = c.matrix().unwrap();
+        let half = |n: i32| Rational32::new(n, 2);
+        assert_eq!(m, [[r(1), half(1), half(3)], [half(1), r(-2), r(-2)], [half(3), r(-2), r(5)]]);
+        assert_eq!(Conic::from_matrix(&m), Some(c));
+        assert_eq!(Conic::from_matrix(&[[r(1), r(1), r(0)], [r(0), r(1), r(0)], [r(0), r(0), r(1)]]), None);
+    }
+
+    #[test]
+    fn to_string() {
+        assert_eq!(conic("(x - 1)^2 + y^2 = 25").unwrap().to_string(), "x^2 + y^2 - 2*x - 24 = 0");
+        assert_eq!(conic("x*y/2 = 3/4").unwrap().to_string(), "1/2*x*y - 3/4 = 0");
+        assert_eq!(conic("x = x").unwrap().to_string(), "0 = 0");
+    }
+}
@@ -0,0 +1,174 @@
+// Symbolic differentiation of expressions.
+//
+// The derivative is built with the sum, product, quotient and power
+// rules and then simplified. Powers are only differentiated when the
+// exponent does not depend on the variable, because u^v in general
+// needs logarithms that the language cannot express. The functions
+// sqrt, abs and sq are differentiated with the chain rule; abs is not
+// differentiable at 0 and its derivative abs(u)/u is undefined there.
+
+use crate::expression::Expression;
+use crate::expression::Equation;
+use crate::expression::Operator;
+use crate::simplify::simplify;
+
+pub fn differentiate(e: &Expression, variable: &str) -> Option<Expression> {
+    Some(simplify(&derivative(e, variable)?))
+}
+
+// The slope dy/dx = -F_x / F_y of the curve F(x, y) = 0 described by
+// the equation, where F = left - right.
+pub fn implicit_derivative(e: &Equation) -> Option<Expression> {
+    let f = e.residual();
+    let f_x = derivative(&f, "x")?;
+    let f_y = derivative(&f, "y")?;
+    Some(simplify(&Expression::negation(Expression::composite(f_x, Operator::DIV, f_y))))
+}
+
+fn derivative(e: &Expression, variable: &str) -> Option<Expression> {
+    match e {
+        Expression::Number(_) => Some(Expression::number(0)),
+        Expression::Variable(v) => Some(Expression::number(if v == variable { 1 } else { 0 })),
+        Expression::Negation(u) => Some(Expression::negation(derivative(u, variable)?)),
+        Expression::Composite(c) => {
+            let u = (*c.left).clone();
+            let v = (*c.right).clone();
+            match c.operator {
+                Operator::PLUS | Operator::MINUS => {
+                    let du = derivative(&u, variable)?;
+                    let dv = derivative(&v, variable)?;
+                    Some(Expression::composite(du, c.operator.clone(), dv))
+                },
+                Operator::MULT => {
+                    let du = derivative(&u, variable)?;
+                    let dv = derivative(&v, variable)?;
+                    Some(Expression::composite(
+                        Expression::composite(du, Operator::MULT, v),
+                        Operator::PLUS,
+                        Expression::composite(u, Operator::MULT, dv)))
+                },
+                Operator::DIV => {
+                    let du = derivative(&u, variable)?;
+                    let dv = derivative(&v, variable)?;
+                    let numerator = Expression::composite(
+                        Expression::composite(du, Operator::MULT, v.clone()),
+                        Operator::MINUS,
+                        Expression::composite(u, Operator::MULT, dv));
+                    let denominator = Expression::composite(v, Operator::POW, Expression::number(2));
+                    Some(Expression::composite(numerator, Operator::DIV, denominator))
+                },
+                Operator::POW => {
+                    if v.variables().contains(variable) {
+                        return None;
+                    }
+                    let du = derivative(&u, variable)?;
+                    let lowered = Expression::composite(v.clone(), Operator::MINUS, Expression::number(1));
+                    Some(Expression::composite(
+                        Expression::composite(v, Operator::MULT, Expression::composite(u, Operator::POW, lowered)),
+                        Operator::MULT,
+                        du))
+                }
+            }
+        },
+        Expression::Call(c) => {
+            if c.arguments.len() != 1 {
+                return None;
+            }
+            let u = c.arguments[0].clone();
+            let du = derivative(&u, variable)?;
+            let outer = match c.name.as_str() {
+                // 1 / (2 sqrt(u))
+                "sqrt" => Expression::composite(
+                    Expression::number(1),
+                    Operator::DIV,
+                    Expression::composite(Expression::number(2), Operator::MULT, Expression::call("sqrt", vec![u]))),
+                // abs(u) / u, the sign of u
+                "abs" => Expression::composite(Expression::call("abs", vec![u.clone()]), Operator::DIV, u),
+                "sq" => Expression::composite(Expression::number(2), Operator::MULT, u),
+                _ => return None
+            };
+            Some(Expression::composite(outer, Operator::MULT, du))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use ::num_rational::Rational32;
+    use super::differentiate;
+    use super::implicit_derivative;
+    use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
+    use crate::expression::Expression;
+    use crate::evaluate::evaluate;
+
+    fn differentiate_and_expect(input: &str, variable: &str, expected: &str) {
+        let e = ExprParser::new().parse(input).unwrap();
+        assert_eq!(differentiate(&e, variable).unwrap().to_string(), expected);
+    }
+
+    fn slope_at(input: &str, x: i32, y: i32) -> Option<Rational32> {
+        let e = EquationParser::new().parse(input).unwrap();
+        let mut values = HashMap::new();
+        values.insert(String::from("x"), Rational32::from(x));
+        values.insert(String::from("y"), Rational32::from(y));
+        evaluate(&implicit_derivative(&e).unwrap(), &values)
+    }
+
+    #[test]
+    fn power_rule() {
+        differentiate_and_expect("x^3", "x", "(3 * (x ^ 2))");
+        differentiate_and_expect("x^-1", "x", "(-(x ^ (-2)))");
+        differentiate_and_expect("3/4*x^2 + 2*x + 1", "x", "(((3 / 2) * x) + 2)");
+    }
+
+    #[test]
+    fn partial_derivatives() {
+        differentiate_and_expect("x^2*y + y^2", "x", "(2 * (x * y))");
+        differentiate_and_expect("x^2*y + y^2", "y", "((x ^ 2) + (2 * y))");
+    }
+
+    #[test]
+    fn chain_rule_for_powers() {
+        differentiate_and_expect("(2*x + 1)^2", "x", "(4 * ((2 * x) + 1))");
+    }
+
+    #[test]
+    fn quotient_rule() {
+        differentiate_and_expect("1/x", "x", "((-1) / (x ^ 2))");
+    }
+
+    #[test]
+    fn exponent_depends_on_variable() {
+        let e = ExprParser::new().parse("2^x").unwrap();
+        assert_eq!(differentiate(&e, "x"), None);
+        assert_eq!(differentiate(&e, "y"), Some(Expression::number(0)));
+    }
+
+    #[test]
+    fn chain_rule_for_functions() {
+        differentiate_and_expect("sq(3*x)", "x", "(18 * x)");
+        differentiate_and_expect("sqrt(x)", "x", "(1 / (2 * sqrt(x)))");
+        differentiate_and_expect("abs(x)", "y", "0");
+        let e = ExprParser::new().parse("f(x)").unwrap();
+        assert_eq!(differentiate(&e, "x"), None);
+    }
+
+    #[test]
+    fn implicit_derivative_of_circle() {
+        let e = EquationParser::new().parse("x^2 + y^2 = 25").unwrap();
+        assert_eq!(implicit_derivative(&e).unwrap().to_string(), "(-((2 * x) / (2 * y)))");
+        assert_eq!(slope_at("x^2 + y^2 = 25", 3, 4), Some(Rational32::new(-3, 4)));
+        assert_eq!(slope_at("x^2 + y^2 = 25", 5, 0), None);
+    }
+
+    #[test]
+    fn implicit_derivative_of_conics() {
+        assert_eq!(slope_at("x^2/4 + y^2/9 = 1", 0, 3), Some(Rational32::from(0)));
+        assert_eq!(slope_at("x^2 - y^2 = 1", 1, 0), None);
+        assert_eq!(slope_at("y^2 = 4*x", 1, 2), Some(Rational32::from(1)));
+        assert_eq!(slope_at("x*y = 6", 2, 3), Some(Rational32::new(-3, 2)));
+        assert_eq!(slope_at("x^2 + x*y + y^2 = 7", 1, 2), Some(Rational32::new(-4, 5)));
+    }
+}
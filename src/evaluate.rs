@@ -0,0 +1,197 @@
+// Evaluate an expression exactly for given values of its variables.
+//
+// The result is None when a variable has no value, when dividing by
+// zero, when an exponent is not an integer, when calling an unknown
+// function, when the result is irrational or when the calculation
+// overflows.
+
+use std::collections::HashMap;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::One;
+use num_traits::Zero;
+use crate::expression::Expression;
+use crate::expression::Operator;
+use crate::visit::Fold;
+use crate::visit::fold;
+use crate::surd::Surd;
+
+pub fn evaluate(e: &Expression, values: &HashMap<String, Rational32>) -> Option<Rational32> {
+    evaluate_surd(e, values)?.to_rational()
+}
+
+// Like evaluate, but square roots are kept exactly, so sqrt(8) + 1
+// evaluates to 1 + 2*sqrt(2).
+pub fn evaluate_surd(e: &Expression, values: &HashMap<String, Rational32>) -> Option<Surd> {
+    fold(e, &mut Evaluator{values})
+}
+
+struct Evaluator<'a> {
+    values: &'a HashMap<String, Rational32>
+}
+
+impl Fold for Evaluator<'_> {
+    type Output = Option<Surd>;
+
+    fn fold_number(&mut self, number: &str) -> Option<Surd> {
+        number.parse::<i32>().ok().map(|n| Surd::from_rational(Rational32::from(n)))
+    }
+
+    fn fold_variable(&mut self, name: &str) -> Option<Surd> {
+        self.values.get(name).copied().map(Surd::from_rational)
+    }
+
+    fn fold_negation(&mut self, inner: Option<Surd>) -> Option<Surd> {
+        inner?.checked_neg()
+    }
+
+    fn fold_composite(&mut self, operator: &Operator, left: Option<Surd>, right: Option<Surd>) -> Option<Surd> {
+        let left = left?;
+        let right = right?;
+        match operator {
+            Operator::PLUS => left.checked_add(&right),
+            Operator::MINUS => left.checked_sub(&right),
+            Operator::MULT => left.checked_mul(&right),
+            Operator::DIV => left.checked_div(&right),
+            Operator::POW => {
+                let exponent = right.to_rational()?;
+                if !exponent.is_integer() {
+                    return None;
+                }
+                left.checked_pow(*exponent.numer())
+            }
+        }
+    }
+
+    fn fold_call(&mut self, name: &str, arguments: Vec<Option<Surd>>) -> Option<Surd> {
+        let arguments: Option<Vec<Surd>> = arguments.into_iter().collect();
+        apply_function(name, &arguments?)
+    }
+}
+
+// The functions that can be called: sqrt, abs and sq (the square).
+// The square root is only taken of non-negative rationals, through
+// sqrt::sqrt.
+pub fn apply_function(name: &str, arguments: &[Surd]) -> Option<Surd> {
+    let argument = match arguments {
+        [argument] => argument,
+        _ => return None
+    };
+    match name {
+        "sqrt" => argument.checked_sqrt(),
+        "abs" => argument.checked_abs(),
+        "sq" => argument.checked_mul(argument),
+        _ => None
+    }
+}
+
+pub fn apply(operator: &Operator, left: Rational32, right: Rational32) -> Option<Rational32> {
+    match operator {
+        Operator::PLUS => left.checked_add(&right),
+        Operator::MINUS => left.checked_sub(&right),
+        Operator::MULT => left.checked_mul(&right),
+        Operator::DIV => {
+            if right.is_zero() {
+                return None;
+            }
+            left.checked_div(&right)
+        },
+        Operator::POW => {
+            if !right.is_integer() {
+                return None;
+            }
+            checked_pow(left, *right.numer())
+        }
+    }
+}
+
+pub fn checked_neg(r: Rational32) -> Option<Rational32> {
+    r.checked_mul(&Rational32::from(-1))
+}
+
+// Exponentiation by squaring, so that large exponents fail fast
+// with an overflow instead of looping.
+pub fn checked_pow(base: Rational32, exponent: i32) -> Option<Rational32> {
+    if exponent < 0 {
+        if base.is_zero() {
+            return None;
+        }
+        return checked_pow(base.recip(), exponent.checked_neg()?);
+    }
+    let mut result = Rational32::one();
+    let mut square = base;
+    let mut remaining = exponent as u32;
+    while remaining > 0 {
+        if remaining % 2 == 1 {
+            result = result.checked_mul(&square)?;
+        }
+        remaining /= 2;
+        if remaining > 0 {
+            square = square.checked_mul(&square)?;
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+
+    fn evaluate_at(input: &str, x: Rational32) -> Option<Rational32> {
+        let mut values = HashMap::new();
+        values.insert(String::from("x"), x);
+        evaluate(&ExprParser::new().parse(input).unwrap(), &values)
+    }
+
+    #[test]
+    fn evaluate_polynomial() {
+        assert_eq!(evaluate_at("3/4*x^2 - x + 1", Rational32::from(2)), Some(Rational32::from(2)));
+    }
+
+    #[test]
+    fn negative_exponent() {
+        assert_eq!(evaluate_at("x^-2", Rational32::new(2, 3)), Some(Rational32::new(9, 4)));
+    }
+
+    #[test]
+    fn undefined() {
+        assert_eq!(evaluate_at("1/(x-1)", Rational32::from(1)), None);
+        assert_eq!(evaluate_at("y", Rational32::from(1)), None);
+        assert_eq!(evaluate_at("2^(1/2)", Rational32::from(1)), None);
+        assert_eq!(evaluate_at("0^-1", Rational32::from(1)), None);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(evaluate_at("sqrt(x^2 + 9)", Rational32::from(4)), Some(Rational32::from(5)));
+        assert_eq!(evaluate_at("abs(1 - x) + sq(x)", Rational32::from(3)), Some(Rational32::from(11)));
+        assert_eq!(evaluate_at("sqrt(x)", Rational32::from(2)), None);
+        assert_eq!(evaluate_at("sqrt(x)", Rational32::from(-4)), None);
+        assert_eq!(evaluate_at("foo(x)", Rational32::from(1)), None);
+        assert_eq!(evaluate_at("abs(x, x)", Rational32::from(1)), None);
+    }
+
+    #[test]
+    fn surds() {
+        let e = ExprParser::new().parse("(1 + sqrt(8))^2 / sqrt(x)").unwrap();
+        let mut values = HashMap::new();
+        values.insert(String::from("x"), Rational32::from(2));
+        assert_eq!(evaluate_surd(&e, &values).unwrap().to_string(), "4 + 9/2*sqrt(2)");
+        let e = ExprParser::new().parse("(1 + sqrt(2)) / 50000").unwrap();
+        assert_eq!(evaluate_surd(&e, &values).unwrap().to_string(), "1/50000 + 1/50000*sqrt(2)");
+        assert_eq!(evaluate_at("sqrt(2)*sqrt(8)", Rational32::from(1)), Some(Rational32::from(4)));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(evaluate_at("x^1000000", Rational32::from(2)), None);
+        assert_eq!(evaluate_at("x^1000000", Rational32::from(-1)), Some(Rational32::from(1)));
+        // Dividing by a large rational must not square it.
+        assert_eq!(evaluate_at("1/50000", Rational32::from(1)), Some(Rational32::new(1, 50000)));
+        assert_eq!(evaluate_at("100000/50000", Rational32::from(1)), Some(Rational32::from(2)));
+    }
+}
@@ -1,15 +1,35 @@
+use ::num_rational::Rational32;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::Signed;
+use num_traits::Zero;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use crate::visit::Fold;
+use crate::visit::fold;
+use crate::visit::Visitor;
+
+// The derived ordering is the canonical ordering used by
+// canonical::canonical to sort the operands of + and *.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Expression {
     Number(String),
-    Composite(Composite)
+    Variable(String),
+    Negation(Box<Expression>),
+    Composite(Composite),
+    Call(Call)
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Operator {
     PLUS,
     MINUS,
     MULT,
-    DIV
+    DIV,
+    POW
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Composite {
     pub operator: Operator,
     pub left: Box<Expression>,
@@ -22,32 +42,240 @@ impl Composite {
     }
 }
 
+// A call of a named function like sqrt(x). The names are not checked
+// by the parser; evaluation fails for unknown functions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Call {
+    pub name: String,
+    pub arguments: Vec<Expression>
+}
+
+impl Call {
+    pub fn new(name: String, arguments: Vec<Expression>) -> Call {
+        return Call{name, arguments};
+    }
+}
+
+// How strongly an expression binds when it is printed with
+// only the parentheses that are needed. Higher binds stronger.
+pub const PRECEDENCE_SUM: u32 = 1;
+pub const PRECEDENCE_PRODUCT: u32 = 2;
+pub const PRECEDENCE_NEGATION: u32 = 3;
+pub const PRECEDENCE_POWER: u32 = 4;
+pub const PRECEDENCE_ATOM: u32 = 5;
+
+impl Operator {
+    pub fn precedence(&self) -> u32 {
+        match self {
+            Operator::PLUS | Operator::MINUS => PRECEDENCE_SUM,
+            Operator::MULT | Operator::DIV => PRECEDENCE_PRODUCT,
+            Operator::POW => PRECEDENCE_POWER
+        }
+    }
+}
+
+impl Expression {
+    pub fn number(n: u32) -> Expression {
+        Expression::Number(n.to_string())
+    }
+
+    pub fn variable(name: &str) -> Expression {
+        Expression::Variable(String::from(name))
+    }
+
+    pub fn negation(e: Expression) -> Expression {
+        Expression::Negation(Box::new(e))
+    }
+
+    pub fn composite(left: Expression, operator: Operator, right: Expression) -> Expression {
+        Expression::Composite(Composite::new(Box::new(left), operator, Box::new(right)))
+    }
+
+    pub fn call(name: &str, arguments: Vec<Expression>) -> Expression {
+        Expression::Call(Call::new(String::from(name), arguments))
+    }
+
+    // The literal that the parser would produce for a rational:
+    // n, -n, n / d or -(n / d).
+    pub fn from_rational(r: Rational32) -> Expression {
+        let numer = Expression::Number(r.numer().unsigned_abs().to_string());
+        let magnitude = if r.is_integer() {
+            numer
+        } else {
+            Expression::composite(numer, Operator::DIV, Expression::Number(r.denom().to_string()))
+        };
+        if r.is_negative() {
+            Expression::negation(magnitude)
+        } else {
+            magnitude
+        }
+    }
+
+    // The value of a constant built from number literals, negation
+    // and division, like the ones produced by from_rational.
+    pub fn as_rational(&self) -> Option<Rational32> {
+        match self {
+            Expression::Number(s) => s.parse::<i32>().ok().map(Rational32::from),
+            Expression::Negation(e) => e.as_rational().and_then(|r| r.checked_mul(&Rational32::from(-1))),
+            Expression::Composite(c) if matches!(c.operator, Operator::DIV) => {
+                let left = c.left.as_rational()?;
+                let right = c.right.as_rational()?;
+                if right.is_zero() {
+                    return None;
+                }
+                left.checked_div(&right)
+            },
+            _ => None
+        }
+    }
+
+    // The names of all variables that appear in the expression.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut collector = VariableCollector{variables: BTreeSet::new()};
+        collector.visit_expression(self);
+        collector.variables
+    }
+
+    // Replace variables by expressions. Variables without a
+    // replacement are kept.
+    pub fn substitute(&self, replacements: &HashMap<String, Expression>) -> Expression {
+        fold(self, &mut Substitution{replacements})
+    }
+
+    pub fn precedence(&self) -> u32 {
+        match self {
+            Expression::Number(_) | Expression::Variable(_) | Expression::Call(_) => PRECEDENCE_ATOM,
+            Expression::Negation(_) => PRECEDENCE_NEGATION,
+            Expression::Composite(c) => c.operator.precedence()
+        }
+    }
+}
+
+struct VariableCollector {
+    variables: BTreeSet<String>
+}
+
+impl Visitor for VariableCollector {
+    fn visit_variable(&mut self, name: &str) {
+        self.variables.insert(name.to_owned());
+    }
+}
+
+struct Substitution<'a> {
+    replacements: &'a HashMap<String, Expression>
+}
+
+impl Fold for Substitution<'_> {
+    type Output = Expression;
+
+    fn fold_number(&mut self, number: &str) -> Expression {
+        Expression::Number(number.to_owned())
+    }
+
+    fn fold_variable(&mut self, name: &str) -> Expression {
+        match self.replacements.get(name) {
+            Some(replacement) => replacement.clone(),
+            None => Expression::Variable(name.to_owned())
+        }
+    }
+
+    fn fold_negation(&mut self, inner: Expression) -> Expression {
+        Expression::negation(inner)
+    }
+
+    fn fold_composite(&mut self, operator: &Operator, left: Expression, right: Expression) -> Expression {
+        Expression::composite(left, operator.clone(), right)
+    }
+
+    fn fold_call(&mut self, name: &str, arguments: Vec<Expression>) -> Expression {
+        Expression::call(name, arguments)
+    }
+}
+
+// An equation left = right. A curve in the plane is described
+// by an equation in the variables x and y.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Equation {
+    pub left: Box<Expression>,
+    pub right: Box<Expression>
+}
+
+impl Equation {
+    pub fn new(left: Box<Expression>, right: Box<Expression>) -> Equation {
+        return Equation{left, right};
+    }
+
+    // left - right, which is zero exactly on the points of the curve.
+    pub fn residual(&self) -> Expression {
+        Expression::composite((*self.left).clone(), Operator::MINUS, (*self.right).clone())
+    }
+}
+
 impl ToString for Operator {
     fn to_string(&self) -> String {
         match self {
             Operator::PLUS => String::from("+"),
             Operator::MINUS => String::from("-"),
             Operator::MULT => String::from("*"),
-            Operator::DIV => String::from("/")
+            Operator::DIV => String::from("/"),
+            Operator::POW => String::from("^")
         }
     }
 }
 
+// Fully parenthesized form, so that the structure of the tree is visible.
+struct Printer;
+
+impl Fold for Printer {
+    type Output = String;
+
+    fn fold_number(&mut self, number: &str) -> String {
+        number.to_owned()
+    }
+
+    fn fold_variable(&mut self, name: &str) -> String {
+        name.to_owned()
+    }
+
+    fn fold_negation(&mut self, inner: String) -> String {
+        let mut result: String = "(-".to_owned();
+        result.push_str(&inner);
+        result.push(')');
+        result
+    }
+
+    fn fold_composite(&mut self, operator: &Operator, left: String, right: String) -> String {
+        let mut result: String = "(".to_owned();
+        result.push_str(&left);
+        result.push_str(" ");
+        result.push_str(&operator.to_string());
+        result.push_str(" ");
+        result.push_str(&right);
+        result.push_str(")");
+        result
+    }
+
+    fn fold_call(&mut self, name: &str, arguments: Vec<String>) -> String {
+        let mut result: String = name.to_owned();
+        result.push('(');
+        result.push_str(&arguments.join(", "));
+        result.push(')');
+        result
+    }
+}
+
 impl ToString for Expression {
     fn to_string(&self) -> String {
-        match self {
-            Expression::Number(s) => s.to_owned(),
-            Expression::Composite(c) => {
-                let mut result: String = "(".to_owned();
-                result.push_str(&c.left.to_string());
-                result.push_str(" ");
-                result.push_str(&c.operator.to_string());
-                result.push_str(" ");
-                result.push_str(&c.right.to_string());
-                result.push_str(")");
-                result
-            }
-        }
+        fold(self, &mut Printer)
+    }
+}
+
+impl ToString for Equation {
+    fn to_string(&self) -> String {
+        let mut result = self.left.to_string();
+        result.push_str(" = ");
+        result.push_str(&self.right.to_string());
+        result
     }
 }
 
@@ -56,6 +284,9 @@ mod test {
     use crate::expression::Expression;
     use crate::expression::Composite;
     use crate::expression::Operator;
+    use crate::expression::Equation;
+    use crate::lang::ExprParser;
+    use std::collections::HashMap;
 
     #[test]
     fn expression_to_string() {
@@ -66,4 +297,28 @@ mod test {
         });
         assert_eq!(e.to_string(), "(5 + 3)");
     }
+
+    #[test]
+    fn variables() {
+        let e = ExprParser::new().parse("x^2 + a*y - x/b").unwrap();
+        let names: Vec<String> = e.variables().into_iter().collect();
+        assert_eq!(names, vec!["a", "b", "x", "y"]);
+    }
+
+    #[test]
+    fn substitute() {
+        let e = ExprParser::new().parse("x^2 + a*y").unwrap();
+        let mut replacements = HashMap::new();
+        replacements.insert(String::from("a"), *ExprParser::new().parse("3/4").unwrap());
+        replacements.insert(String::from("x"), *ExprParser::new().parse("x - 1").unwrap());
+        assert_eq!(e.substitute(&replacements).to_string(), "(((x - 1) ^ 2) + ((3 / 4) * y))");
+    }
+
+    #[test]
+    fn equation_to_string() {
+        let e = Equation::new(
+            Box::new(Expression::Negation(Box::new(Expression::Variable(String::from("x"))))),
+            Box::new(Expression::Number(String::from("3"))));
+        assert_eq!(e.to_string(), "(-x) = 3");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,205 @@
+// Conics through given points.
+//
+// Each point gives a linear equation for the unknown coefficients of
+// the conic, so the conics through the points are the nullspace of a
+// matrix with a row per point. Five points in general position give a
+// single conic up to a factor. Constrained families like circles or
+// parabolas with a given axis have fewer unknowns and need fewer points.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::Signed;
+use num_traits::Zero;
+use crate::conic::Conic;
+use crate::conic::ConicKind;
+use crate::linalg::Matrix;
+use crate::primes::gcd;
+use crate::primes::lcm;
+
+pub type Point = (Rational32, Rational32);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fit {
+    // The only conic through the points, which is an ellipse, a
+    // hyperbola or a parabola.
+    Unique(Conic),
+    // The only conic through the points is degenerate, like the line
+    // pair through five points of which three are on a line.
+    Degenerate(Conic),
+    // The conics through the points form a family with this many
+    // independent members, like when four of five points are on a line
+    // or points repeat.
+    NotUnique(usize),
+    // No conic of the required kind passes through the points, like a
+    // circle through three points on a line.
+    Impossible
+}
+
+// The conic through five points. Fewer points give a family, and more
+// points may be on no conic at all. None on overflow.
+pub fn conic_through(points: &[Point]) -> Option<Fit> {
+    let rows = points.iter()
+        .map(|(x, y)| Some(vec![x.checked_mul(x)?, x.checked_mul(y)?, y.checked_mul(y)?, *x, *y, Rational32::from(1)]))
+        .collect::<Option<Vec<_>>>()?;
+    let solutions = Matrix::from_rows(rows)?.nullspace()?;
+    if solutions.is_empty() {
+        return Some(Fit::Impossible);
+    }
+    if solutions.len() != 1 {
+        return Some(Fit::NotUnique(solutions.len()));
+    }
+    let c = primitive(&solutions[0])?;
+    classify(Conic::new(c[0], c[1], c[2], c[3], c[4], c[5]))
+}
+
+// The circle x^2 + y^2 + D x + E y + F = 0 through three points, which
+// is impossible when they are on a line.
+pub fn circle_through(points: &[Point; 3]) -> Option<Fit> {
+    let rows = points.iter()
+        .map(|(x, y)| Some(vec![x.checked_mul(x)?.checked_add(&y.checked_mul(y)?)?, *x, *y, Rational32::from(1)]))
+        .collect::<Option<Vec<_>>>()?;
+    let solutions = Matrix::from_rows(rows)?.nullspace()?;
+    if solutions.len() != 1 {
+        return Some(Fit::NotUnique(solutions.len()));
+    }
+    let c = primitive(&solutions[0])?;
+    if c[0].is_zero() {
+        return Some(Fit::Impossible);
+    }
+    classify(Conic::new(c[0], Rational32::zero(), c[0], c[1], c[2], c[3]))
+}
+
+// The parabola with an axis in the direction (p, q) through three
+// points. Its quadratic part is a multiple of (q x - p y)^2, so it is
+// L (q x - p y)^2 + D x + E y + F = 0. None for the direction (0, 0)
+// and on overflow.
+pub fn parabola_through(points: &[Point; 3], axis: Point) -> Option<Fit> {
+    let (p, q) = axis;
+    if p.is_zero() && q.is_zero() {
+        return None;
+    }
+    let rows = points.iter()
+        .map(|(x, y)| {
+            let across = q.checked_mul(x)?.checked_sub(&p.checked_mul(y)?)?;
+            Some(vec![across.checked_mul(&across)?, *x, *y, Rational32::from(1)])
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let solutions = Matrix::from_rows(rows)?.nullspace()?;
+    if solutions.len() != 1 {
+        return Some(Fit::NotUnique(solutions.len()));
+    }
+    let c = primitive(&solutions[0])?;
+    if c[0].is_zero() {
+        return Some(Fit::Impossible);
+    }
+    let l = c[0];
+    let two = Rational32::from(2);
+    classify(Conic::new(
+        l.checked_mul(&q.checked_mul(&q)?)?,
+        l.checked_mul(&two.checked_mul(&p)?.checked_mul(&q)?)?.checked_mul(&Rational32::from(-1))?,
+        l.checked_mul(&p.checked_mul(&p)?)?,
+        c[1],
+        c[2],
+        c[3]))
+}
+
+fn classify(conic: Conic) -> Option<Fit> {
+    Some(match conic.kind()? {
+        ConicKind::Ellipse | ConicKind::Hyperbola | ConicKind::Parabola => Fit::Unique(conic),
+        _ => Fit::Degenerate(conic)
+    })
+}
+
+// The multiple with coprime integer entries and a positive first
+// nonzero entry.
+fn primitive(v: &[Rational32]) -> Option<Vec<Rational32>> {
+    let mut denominator: i32 = 1;
+    for c in v {
+        denominator = lcm(denominator, *c.denom())?;
+    }
+    let scaled = v.iter().map(|c| c.checked_mul(&Rational32::from(denominator))).collect::<Option<Vec<_>>>()?;
+    let mut content = scaled.iter().fold(0, |g, c| gcd(g, *c.numer()));
+    if content == 0 {
+        return Some(scaled);
+    }
+    if scaled.iter().find(|c| !c.is_zero()).map(|c| c.signum().cmp(&Rational32::zero())) == Some(Ordering::Less) {
+        content = content.checked_neg()?;
+    }
+    scaled.iter().map(|c| c.checked_div(&Rational32::from(content))).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn points(coordinates: &[(i32, i32)]) -> Vec<Point> {
+        coordinates.iter().map(|(x, y)| (Rational32::from(*x), Rational32::from(*y))).collect()
+    }
+
+    fn unique(fit: Option<Fit>) -> String {
+        match fit.unwrap() {
+            Fit::Unique(conic) => conic.to_string(),
+            other => panic!("Expected a unique conic, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn five_points() {
+        assert_eq!(unique(conic_through(&points(&[(5, 0), (0, 5), (-5, 0), (0, -5), (3, 4)]))), "x^2 + y^2 - 25 = 0");
+        let half = Rational32::new(1, 2);
+        let (one, two) = (Rational32::from(1), Rational32::from(2));
+        let hyperbola = [(one, one), (two, half), (-one, -one), (half, two), (-two, -half)];
+        assert_eq!(unique(conic_through(&hyperbola)), "x*y - 1 = 0");
+        assert_eq!(unique(conic_through(&points(&[(0, 0), (1, 1), (-1, 1), (2, 4), (3, 9)]))), "x^2 - y = 0");
+    }
+
+    #[test]
+    fn degenerate_and_not_unique() {
+        // Three points on y = 0 and two on y = 1.
+        let fit = conic_through(&points(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)]));
+        assert_eq!(fit.map(|f| match f { Fit::Degenerate(c) => c.to_string(), _ => String::new() }), Some("y^2 - y = 0".to_owned()));
+        // Four points on y = 0 leave the second line free.
+        assert_eq!(conic_through(&points(&[(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)])), Some(Fit::NotUnique(2)));
+        assert_eq!(conic_through(&points(&[(5, 0), (0, 5), (-5, 0), (0, -5), (5, 0)])), Some(Fit::NotUnique(2)));
+        assert_eq!(conic_through(&points(&[(1, 2), (3, 4)])), Some(Fit::NotUnique(4)));
+    }
+
+    #[test]
+    fn more_than_five_points() {
+        let circle = [(5, 0), (0, 5), (-5, 0), (0, -5), (3, 4), (-4, 3)];
+        assert_eq!(unique(conic_through(&points(&circle))), "x^2 + y^2 - 25 = 0");
+        let off_circle = [(5, 0), (0, 5), (-5, 0), (0, -5), (3, 4), (1, 1)];
+        assert_eq!(conic_through(&points(&off_circle)), Some(Fit::Impossible));
+    }
+
+    #[test]
+    fn circle() {
+        let three = |c: &[(i32, i32)]| [0, 1, 2].map(|i| (Rational32::from(c[i].0), Rational32::from(c[i].1)));
+        assert_eq!(unique(circle_through(&three(&[(1, 0), (0, 1), (-1, 0)]))), "x^2 + y^2 - 1 = 0");
+        assert_eq!(unique(circle_through(&three(&[(0, 0), (2, 0), (0, 4)]))), "x^2 + y^2 - 2*x - 4*y = 0");
+        assert_eq!(circle_through(&three(&[(0, 0), (1, 1), (2, 2)])), Some(Fit::Impossible));
+        assert_eq!(circle_through(&three(&[(0, 0), (0, 0), (2, 2)])), Some(Fit::NotUnique(2)));
+    }
+
+    #[test]
+    fn parabola() {
+        let three = |c: &[(i32, i32)]| [0, 1, 2].map(|i| (Rational32::from(c[i].0), Rational32::from(c[i].1)));
+        let up = (Rational32::from(0), Rational32::from(1));
+        assert_eq!(unique(parabola_through(&three(&[(0, 0), (1, 1), (-1, 1)]), up)), "x^2 - y = 0");
+        let diagonal = (Rational32::from(1), Rational32::from(1));
+        assert_eq!(unique(parabola_through(&three(&[(0, 0), (1, 0), (0, 1)]), diagonal)), "x^2 - 2*x*y + y^2 - x - y = 0");
+        // The points are on y = x, and a line meets a conic in at most
+        // two points.
+        assert_eq!(parabola_through(&three(&[(0, 0), (1, 1), (2, 2)]), up), Some(Fit::Impossible));
+        let fit = parabola_through(&three(&[(0, 0), (0, 1), (1, 1)]), up);
+        assert_eq!(fit, Some(Fit::Degenerate(Conic::from_matrix(&[
+            [Rational32::from(1), Rational32::from(0), Rational32::new(-1, 2)],
+            [Rational32::from(0), Rational32::from(0), Rational32::from(0)],
+            [Rational32::new(-1, 2), Rational32::from(0), Rational32::from(0)]]).unwrap())));
+        assert_eq!(parabola_through(&three(&[(0, 0), (0, 1), (1, 1)]), (Rational32::from(0), Rational32::from(0))), None);
+    }
+}
@@ -0,0 +1,324 @@
+// Decide whether a student's answer is mathematically equivalent to
+// the expected answer.
+//
+// When both sides are polynomials, their normal forms are compared
+// exactly. Otherwise, for example when dividing by a variable, both
+// sides are evaluated at pseudo-random rational points; the points are
+// generated from a fixed seed so that grading is reproducible. Points
+// where one of the sides is undefined are skipped, and when no point is
+// left the verdict is inconclusive.
+//
+// Equations F = G are compared through their residuals F - G. Two
+// equations describe the same curve when one residual is a nonzero
+// multiple of the other, so 2x = 4 is accepted for x = 2, but
+// reported as equivalent up to a scalar multiple. An answer with its
+// sides swapped has the residual G - F and is equivalent, and so are
+// two identities, whose residuals are both zero.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use ::num_rational::Rational32;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::One;
+use num_traits::Zero;
+use crate::expression::Expression;
+use crate::expression::Equation;
+use crate::evaluate::evaluate;
+use crate::polynomial::Polynomial;
+
+const NUM_SAMPLES: u32 = 32;
+const SEED: u64 = 0x2545f4914f6cdd1d;
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Equivalent,
+    // The answer is the expected equation multiplied by the factor.
+    EquivalentUpToScalar(Rational32),
+    Different(Option<Counterexample>),
+    // No sample point was defined on both sides, like for 1/(x - x), so
+    // nothing could be compared.
+    Inconclusive
+}
+
+// A point where the answer does not match. For expressions the values
+// are the values of both expressions at the point. For equations
+// they are the residuals left - right.
+#[derive(Debug, PartialEq)]
+pub struct Counterexample {
+    pub point: BTreeMap<String, Rational32>,
+    pub expected: Rational32,
+    pub answer: Rational32
+}
+
+pub fn grade_expression(expected: &Expression, answer: &Expression) -> Verdict {
+    if let (Some(p), Some(q)) = (Polynomial::from_expression(expected), Polynomial::from_expression(answer)) {
+        if p == q {
+            return Verdict::Equivalent;
+        }
+        return Verdict::Different(find_counterexample(expected, answer, |e, a| e != a));
+    }
+    match compare_samples(expected, answer, |e, a| e != a) {
+        Comparison::Agree => Verdict::Equivalent,
+        Comparison::Differ(counterexample) => Verdict::Different(Some(counterexample)),
+        Comparison::Undefined => Verdict::Inconclusive
+    }
+}
+
+pub fn grade_equation(expected: &Equation, answer: &Equation) -> Verdict {
+    let expected = expected.residual();
+    let answer = answer.residual();
+    if let (Some(p), Some(q)) = (Polynomial::from_expression(&expected), Polynomial::from_expression(&answer)) {
+        if p.is_zero() && q.is_zero() {
+            return Verdict::Equivalent;
+        }
+        return match q.scalar_multiple_of(&p) {
+            Some(factor) if is_unit(factor) => Verdict::Equivalent,
+            Some(factor) if !factor.is_zero() => Verdict::EquivalentUpToScalar(factor),
+            _ => Verdict::Different(find_equation_counterexample(&expected, &answer))
+        };
+    }
+    let factor = match first_ratio(&expected, &answer) {
+        Some(factor) => factor,
+        // The expected residual is zero wherever it is defined.
+        None => return match compare_samples(&expected, &answer, |_, a| !a.is_zero()) {
+            Comparison::Agree => Verdict::Equivalent,
+            Comparison::Differ(counterexample) => Verdict::Different(Some(counterexample)),
+            Comparison::Undefined => Verdict::Inconclusive
+        }
+    };
+    match compare_samples(&expected, &answer, |e, a| e.checked_mul(&factor) != Some(a)) {
+        Comparison::Agree if is_unit(factor) => Verdict::Equivalent,
+        Comparison::Agree => Verdict::EquivalentUpToScalar(factor),
+        Comparison::Differ(counterexample) => Verdict::Different(Some(counterexample)),
+        Comparison::Undefined => Verdict::Inconclusive
+    }
+}
+
+// A factor of -1 means that the answer's residual right - left is the
+// expected residual, so the sides are only swapped.
+fn is_unit(factor: Rational32) -> bool {
+    factor.is_one() || (-factor).is_one()
+}
+
+// The ratio answer / expected at the first sample point where the
+// expected residual is defined and nonzero.
+fn first_ratio(expected: &Expression, answer: &Expression) -> Option<Rational32> {
+    let variables = all_variables(expected, answer);
+    let mut points = SamplePoints::new(&variables);
+    for _ in 0 .. NUM_SAMPLES {
+        let values = points.next_point();
+        if let (Some(e), Some(a)) = (evaluate(expected, &values), evaluate(answer, &values)) {
+            if !e.is_zero() {
+                return a.checked_div(&e);
+            }
+        }
+    }
+    None
+}
+
+// Prefer a point that lies on exactly one of the curves. Small integer
+// points are tried first, because textbook curves often pass through
+// them. Otherwise report a point where the residuals differ.
+fn find_equation_counterexample(expected: &Expression, answer: &Expression) -> Option<Counterexample> {
+    let variables = all_variables(expected, answer);
+    if variables.len() <= 2 {
+        let names: Vec<&String> = variables.iter().collect();
+        for i in -10 ..= 10 {
+            for j in -10 ..= 10 {
+                let mut values: HashMap<String, Rational32> = HashMap::new();
+                if let Some(name) = names.first() {
+                    values.insert((*name).clone(), Rational32::from(i));
+                }
+                if let Some(name) = names.get(1) {
+                    values.insert((*name).clone(), Rational32::from(j));
+                }
+                if let Some(counterexample) = check_point(expected, answer, &values, |e, a| e.is_zero() != a.is_zero()) {
+                    return Some(counterexample);
+                }
+            }
+        }
+    }
+    find_counterexample(expected, answer, |e, a| e != a)
+}
+
+fn find_counterexample<F>(expected: &Expression, answer: &Expression, differs: F) -> Option<Counterexample>
+        where F: Fn(Rational32, Rational32) -> bool {
+    match compare_samples(expected, answer, differs) {
+        Comparison::Differ(counterexample) => Some(counterexample),
+        _ => None
+    }
+}
+
+// The outcome of comparing both sides at the sample points.
+enum Comparison {
+    // The sides agree at every point where both are defined, and there
+    // is at least one such point.
+    Agree,
+    Differ(Counterexample),
+    Undefined
+}
+
+fn compare_samples<F>(expected: &Expression, answer: &Expression, differs: F) -> Comparison
+        where F: Fn(Rational32, Rational32) -> bool {
+    let variables = all_variables(expected, answer);
+    let mut points = SamplePoints::new(&variables);
+    let mut compared = 0;
+    for _ in 0 .. NUM_SAMPLES {
+        let values = points.next_point();
+        let (Some(e), Some(a)) = (evaluate(expected, &values), evaluate(answer, &values)) else {
+            continue;
+        };
+        if differs(e, a) {
+            return Comparison::Differ(counterexample(&values, e, a));
+        }
+        compared += 1;
+    }
+    if compared == 0 {
+        Comparison::Undefined
+    } else {
+        Comparison::Agree
+    }
+}
+
+fn check_point<F>(expected: &Expression, answer: &Expression, values: &HashMap<String, Rational32>, differs: F) -> Option<Counterexample>
+        where F: Fn(Rational32, Rational32) -> bool {
+    let e = evaluate(expected, values)?;
+    let a = evaluate(answer, values)?;
+    if !differs(e, a) {
+        return None;
+    }
+    Some(counterexample(values, e, a))
+}
+
+fn counterexample(values: &HashMap<String, Rational32>, expected: Rational32, answer: Rational32) -> Counterexample {
+    Counterexample {
+        point: values.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        expected,
+        answer
+    }
+}
+
+fn all_variables(first: &Expression, second: &Expression) -> BTreeSet<String> {
+    let mut variables = first.variables();
+    variables.extend(second.variables());
+    variables
+}
+
+// Pseudo-random points with small numerators and denominators, so that
+// evaluating textbook expressions does not overflow.
+struct SamplePoints<'a> {
+    variables: &'a BTreeSet<String>,
+    state: u64
+}
+
+impl SamplePoints<'_> {
+    fn new(variables: &BTreeSet<String>) -> SamplePoints<'_> {
+        SamplePoints{variables, state: SEED}
+    }
+
+    // xorshift64*
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    fn next_rational(&mut self) -> Rational32 {
+        let numer = (self.next_random() % 41) as i32 - 20;
+        let denom = (self.next_random() % 7) as i32 + 1;
+        Rational32::new(numer, denom)
+    }
+
+    fn next_point(&mut self) -> HashMap<String, Rational32> {
+        let mut values = HashMap::new();
+        for variable in self.variables.iter() {
+            let value = self.next_rational();
+            values.insert(variable.clone(), value);
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
+
+    fn grade(expected: &str, answer: &str) -> Verdict {
+        grade_expression(&ExprParser::new().parse(expected).unwrap(), &ExprParser::new().parse(answer).unwrap())
+    }
+
+    fn grade_equations(expected: &str, answer: &str) -> Verdict {
+        grade_equation(&EquationParser::new().parse(expected).unwrap(), &EquationParser::new().parse(answer).unwrap())
+    }
+
+    fn assert_counterexample(verdict: Verdict) -> Counterexample {
+        match verdict {
+            Verdict::Different(Some(counterexample)) => counterexample,
+            other => panic!("Expected a counterexample, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn equivalent_polynomials() {
+        assert_eq!(grade("(x + 1)^2", "x^2 + 2*x + 1"), Verdict::Equivalent);
+        assert_eq!(grade("x*y/2", "1/2*y*x"), Verdict::Equivalent);
+    }
+
+    #[test]
+    fn different_polynomials() {
+        let counterexample = assert_counterexample(grade("(x + 1)^2", "x^2 + 1"));
+        assert_ne!(counterexample.expected, counterexample.answer);
+        let x = counterexample.point["x"];
+        assert_eq!(counterexample.expected, (x + 1) * (x + 1));
+    }
+
+    #[test]
+    fn rational_functions() {
+        assert_eq!(grade("1/x + 1/y", "(x + y)/(x*y)"), Verdict::Equivalent);
+        assert_eq!(grade("(x^2 - 1)/(x - 1)", "x + 1"), Verdict::Equivalent);
+        assert_counterexample(grade("1/x + 1/y", "2/(x + y)"));
+    }
+
+    #[test]
+    fn undefined_everywhere() {
+        assert_eq!(grade("1/(x-x)", "7"), Verdict::Inconclusive);
+        assert_eq!(grade("1/(x - x) + 1", "x"), Verdict::Inconclusive);
+        assert_eq!(grade("7", "1/(x-x)"), Verdict::Inconclusive);
+    }
+
+    #[test]
+    fn equivalent_equations() {
+        assert_eq!(grade_equations("x^2 + y^2 = 25", "y^2 = 25 - x^2"), Verdict::Equivalent);
+        assert_eq!(grade_equations("x^2/4 + y^2/9 = 1", "9*x^2 + 4*y^2 = 36"), Verdict::EquivalentUpToScalar(Rational32::from(36)));
+        assert_eq!(grade_equations("y = 2*x + 1", "2*x + 1 = y"), Verdict::Equivalent);
+        assert_eq!(grade_equations("y = 1/x", "1/x = y"), Verdict::Equivalent);
+        assert_eq!(grade_equations("x = 2", "4 = 2*x"), Verdict::EquivalentUpToScalar(Rational32::from(-2)));
+    }
+
+    #[test]
+    fn identities() {
+        assert_eq!(grade_equations("x = x", "y = y"), Verdict::Equivalent);
+        assert_eq!(grade_equations("x/x = 1", "y = y"), Verdict::Equivalent);
+        assert_counterexample(grade_equations("x = x", "y = 1"));
+        assert_counterexample(grade_equations("x/x = 1", "y = 1"));
+    }
+
+    #[test]
+    fn different_equations() {
+        let counterexample = assert_counterexample(grade_equations("x^2 + y^2 = 25", "x^2 + y^2 = 5"));
+        assert!(counterexample.expected.is_zero() != counterexample.answer.is_zero());
+        assert_counterexample(grade_equations("x^2 = 1", "x = 1"));
+    }
+
+    #[test]
+    fn rational_function_equations() {
+        assert_eq!(grade_equations("y = 1/x", "2*y = 2/x"), Verdict::EquivalentUpToScalar(Rational32::from(2)));
+        assert_eq!(grade_equations("y = 1/x", "y - 1/x = 0"), Verdict::Equivalent);
+        assert_counterexample(grade_equations("y = 1/x", "y = 1/(2*x)"));
+    }
+}
@@ -0,0 +1,456 @@
+// Intersections of lines and conics.
+//
+// A line through the point P with direction v meets the conic where
+// Q(P + t v) = alpha t^2 + beta t + gamma = 0, a quadratic equation in
+// t that is solved exactly.
+//
+// Two conics meet where the resultant of their equations with respect
+// to y vanishes, a polynomial in x of degree at most 4. Its roots are
+// found exactly when they are rational or roots of a rational quadratic
+// factor, and are isolated into rational intervals with Sturm sequences
+// otherwise.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use num_traits::Signed;
+use crate::conic::Conic;
+use crate::line::Line;
+use crate::poly::IsolatedRoot;
+use crate::poly::Poly;
+use crate::quadratic::solve_quadratic;
+use crate::surd::Real;
+use crate::surd::Surd;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LineIntersection {
+    // No, one or two points where the line crosses the conic.
+    Points(Vec<(Surd, Surd)>),
+    // The line touches the conic in a single point.
+    Tangent((Surd, Surd)),
+    // The line is part of the conic, which is then degenerate.
+    Contained
+}
+
+// None if a = b = 0, on overflow, and when the points are not
+// quadratic surds, which can happen for irrational coefficients of the
+// line.
+pub fn intersect_line(line: &Line, conic: &Conic) -> Option<LineIntersection> {
+    let (point, direction) = parametrize(line)?;
+    let [alpha, beta, gamma] = restrict(conic, point, direction)?;
+    if alpha.is_zero() && beta.is_zero() && gamma.is_zero() {
+        return Some(LineIntersection::Contained);
+    }
+    let roots = solve_quadratic(&alpha, &beta, &gamma)?;
+    let points = roots.iter()
+        .map(|t| Some((
+            point.0.checked_add(&t.checked_mul(&direction.0)?)?,
+            point.1.checked_add(&t.checked_mul(&direction.1)?)?)))
+        .collect::<Option<Vec<_>>>()?;
+    if !alpha.is_zero() && points.len() == 1 {
+        return Some(LineIntersection::Tangent(points[0]));
+    }
+    Some(LineIntersection::Points(points))
+}
+
+// A point on the line and its direction (-b, a).
+fn parametrize(line: &Line) -> Option<((Surd, Surd), (Surd, Surd))> {
+    let point = if !line.b.is_zero() {
+        (Surd::zero(), line.c.checked_neg()?.checked_div(&line.b)?)
+    } else if !line.a.is_zero() {
+        (line.c.checked_neg()?.checked_div(&line.a)?, Surd::zero())
+    } else {
+        return None;
+    };
+    Some((point, (line.b.checked_neg()?, line.a)))
+}
+
+// The coefficients of Q(P + t v) as a polynomial in t:
+//   alpha = A vx^2 + B vx vy + C vy^2
+//   beta = 2A px vx + B (px vy + py vx) + 2C py vy + D vx + E vy
+//   gamma = Q(P)
+pub fn restrict(conic: &Conic, point: (Surd, Surd), direction: (Surd, Surd)) -> Option<[Surd; 3]> {
+    let [a, b, c, d, e, f] = [conic.a, conic.b, conic.c, conic.d, conic.e, conic.f].map(Surd::from_rational);
+    let (px, py) = point;
+    let (vx, vy) = direction;
+    let quadratic = |x: &Surd, y: &Surd, u: &Surd, v: &Surd| -> Option<Surd> {
+        // A xu + B (xv + yu)/2 + C yv, the bilinear form of the quadratic part
+        let half = Surd::from_rational(Rational32::new(1, 2));
+        let mixed = x.checked_mul(v)?.checked_add(&y.checked_mul(u)?)?.checked_mul(&half)?;
+        a.checked_mul(&x.checked_mul(u)?)?
+            .checked_add(&b.checked_mul(&mixed)?)?
+            .checked_add(&c.checked_mul(&y.checked_mul(v)?)?)
+    };
+    let alpha = quadratic(&vx, &vy, &vx, &vy)?;
+    let cross = quadratic(&px, &py, &vx, &vy)?;
+    let linear_v = d.checked_mul(&vx)?.checked_add(&e.checked_mul(&vy)?)?;
+    let beta = cross.checked_add(&cross)?.checked_add(&linear_v)?;
+    let linear_p = d.checked_mul(&px)?.checked_add(&e.checked_mul(&py)?)?;
+    let gamma = quadratic(&px, &py, &px, &py)?.checked_add(&linear_p)?.checked_add(&f)?;
+    Some([alpha, beta, gamma])
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConicIntersection {
+    // Finitely many common points in increasing order of x.
+    Points(Vec<IntersectionPoint>),
+    // The conics share a line or coincide.
+    CommonComponent
+}
+
+// The y coordinate is approximate when x is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntersectionPoint {
+    pub x: Root,
+    pub y: Real
+}
+
+// A real root of the resultant.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Root {
+    Exact(Surd),
+    Isolated(IsolatedRoot)
+}
+
+impl Root {
+    pub fn to_real(&self) -> Real {
+        match self {
+            Root::Exact(s) => Real::Exact(*s),
+            Root::Isolated(root) => Real::Approximate(root.to_f64())
+        }
+    }
+}
+
+// None on overflow and when a point with an exact x has a y that is not
+// a quadratic surd.
+pub fn intersect_conics(first: &Conic, second: &Conic) -> Option<ConicIntersection> {
+    let p = in_y(first);
+    let q = in_y(second);
+    let resultant = match (degree_in_y(&p), degree_in_y(&q)) {
+        (0, 0) => {
+            // Both conics are made of vertical lines.
+            let common = p[0].checked_gcd(&q[0])?;
+            if common.is_zero() || common.degree() > 0 {
+                return Some(ConicIntersection::CommonComponent);
+            }
+            return Some(ConicIntersection::Points(Vec::new()));
+        },
+        (0, _) => p[0].clone(),
+        (_, 0) => q[0].clone(),
+        (1, 1) => p[1].checked_mul(&q[0])?.checked_sub(&p[0].checked_mul(&q[1])?)?,
+        (1, 2) => linear_quadratic_resultant(&p, &q)?,
+        (2, 1) => linear_quadratic_resultant(&q, &p)?,
+        _ => quadratic_resultant(&p, &q)?
+    };
+    if resultant.is_zero() {
+        return Some(ConicIntersection::CommonComponent);
+    }
+    let mut points = Vec::new();
+    for root in real_roots(&resultant)? {
+        match root {
+            Root::Exact(x) => match common_y(&p, &q, &x)? {
+                Some(ys) => points.extend(ys.into_iter()
+                    .map(|y| IntersectionPoint{x: Root::Exact(x), y: Real::Exact(y)})),
+                None => return Some(ConicIntersection::CommonComponent)
+            },
+            Root::Isolated(isolated) => {
+                let ys = approximate_y(&p, &q, isolated.to_f64());
+                let x = Root::Isolated(isolated);
+                points.extend(ys.into_iter()
+                    .map(|y| IntersectionPoint{x: x.clone(), y: Real::Approximate(y)}));
+            }
+        }
+    }
+    Some(ConicIntersection::Points(points))
+}
+
+// The coefficients of 1, y and y^2 as polynomials in x.
+fn in_y(conic: &Conic) -> [Poly; 3] {
+    [
+        Poly::new(vec![conic.f, conic.d, conic.a]),
+        Poly::new(vec![conic.e, conic.b]),
+        Poly::constant(conic.c)
+    ]
+}
+
+fn degree_in_y(p: &[Poly; 3]) -> usize {
+    if !p[2].is_zero() {
+        2
+    } else if !p[1].is_zero() {
+        1
+    } else {
+        0
+    }
+}
+
+// Res(p2 y^2 + p1 y + p0, q2 y^2 + q1 y + q0)
+//   = (p2 q0 - p0 q2)^2 - (p2 q1 - p1 q2)(p1 q0 - p0 q1)
+fn quadratic_resultant(p: &[Poly; 3], q: &[Poly; 3]) -> Option<Poly> {
+    let u = p[2].checked_mul(&q[0])?.checked_sub(&p[0].checked_mul(&q[2])?)?;
+    let v = p[2].checked_mul(&q[1])?.checked_sub(&p[1].checked_mul(&q[2])?)?;
+    let w = p[1].checked_mul(&q[0])?.checked_sub(&p[0].checked_mul(&q[1])?)?;
+    u.checked_mul(&u)?.checked_sub(&v.checked_mul(&w)?)
+}
+
+// Res(p1 y + p0, q2 y^2 + q1 y + q0) = q2 p0^2 - q1 p0 p1 + q0 p1^2
+fn linear_quadratic_resultant(p: &[Poly; 3], q: &[Poly; 3]) -> Option<Poly> {
+    q[2].checked_mul(&p[0])?.checked_mul(&p[0])?
+        .checked_sub(&q[1].checked_mul(&p[0])?.checked_mul(&p[1])?)?
+        .checked_add(&q[0].checked_mul(&p[1])?.checked_mul(&p[1])?)
+}
+
+// The common roots in y of both equations at x, from their greatest
+// common divisor. The inner None means that both vanish on the whole
+// vertical line. Roots of the resultant where the leading coefficients
+// vanish can have no common root at all.
+fn common_y(p: &[Poly; 3], q: &[Poly; 3], x: &Surd) -> Option<Option<Vec<Surd>>> {
+    let at = |c: &[Poly; 3]| -> Option<Vec<Surd>> {
+        let mut values = c.iter().map(|u| u.evaluate_surd(x)).collect::<Option<Vec<_>>>()?;
+        trim(&mut values);
+        Some(values)
+    };
+    let (mut first, mut second) = (at(p)?, at(q)?);
+    if first.is_empty() && second.is_empty() {
+        return Some(None);
+    }
+    while !second.is_empty() {
+        let remainder = surd_remainder(&first, &second)?;
+        first = second;
+        second = remainder;
+    }
+    let zero = Surd::zero();
+    let roots = match first.len() {
+        2 => solve_quadratic(&zero, &first[1], &first[0])?,
+        3 => solve_quadratic(&first[2], &first[1], &first[0])?,
+        _ => Vec::new()
+    };
+    Some(Some(roots))
+}
+
+fn surd_remainder(dividend: &[Surd], divisor: &[Surd]) -> Option<Vec<Surd>> {
+    let mut remainder = dividend.to_vec();
+    while remainder.len() >= divisor.len() {
+        let shift = remainder.len() - divisor.len();
+        let factor = remainder.last()?.checked_div(divisor.last()?)?;
+        for (i, c) in divisor.iter().enumerate() {
+            remainder[shift + i] = remainder[shift + i].checked_sub(&factor.checked_mul(c)?)?;
+        }
+        remainder.pop();
+        trim(&mut remainder);
+    }
+    Some(remainder)
+}
+
+// The roots in y of the first equation at x, or of the second when the
+// first has a lower degree in y, that are closest to solving the other
+// one.
+fn approximate_y(p: &[Poly; 3], q: &[Poly; 3], x: f64) -> Vec<f64> {
+    let (p, q) = if degree_in_y(p) >= degree_in_y(q) { (p, q) } else { (q, p) };
+    let [c, b, a] = [0, 1, 2].map(|i| p[i].evaluate_f64(x));
+    let candidates = if a == 0.0 {
+        vec![-c / b]
+    } else {
+        let discriminant = (b * b - 4.0 * a * c).max(0.0).sqrt();
+        vec![(-b - discriminant) / (2.0 * a), (-b + discriminant) / (2.0 * a)]
+    };
+    let residual = |y: f64| (q[2].evaluate_f64(x) * y * y + q[1].evaluate_f64(x) * y + q[0].evaluate_f64(x)).abs();
+    let best = candidates.iter().map(|y| residual(*y)).fold(f64::INFINITY, f64::min);
+    let tolerance = 1e-9 * (1.0 + candidates.iter().map(|y| y.abs()).fold(0.0, f64::max)).powi(2);
+    let mut result: Vec<f64> = Vec::new();
+    for y in candidates {
+        let r = residual(y);
+        if (r == best || r <= tolerance) && !result.contains(&y) {
+            result.push(y);
+        }
+    }
+    result.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    result
+}
+
+// The distinct real roots in increasing order. The square-free factors
+// are split into rational roots, quadratic factors that are solved
+// exactly and factors whose roots are isolated.
+pub fn real_roots(p: &Poly) -> Option<Vec<Root>> {
+    let mut roots = Vec::new();
+    for (factor, _) in p.square_free_factors()? {
+        let (rational, rest) = factor.rational_roots()?;
+        roots.extend(rational.into_iter().map(|r| Root::Exact(Surd::from_rational(r))));
+        for part in quadratic_factors(&rest)? {
+            if part.degree() <= 2 {
+                let [c, b, a] = [0, 1, 2].map(|i| Surd::from_rational(part.coefficient(i)));
+                roots.extend(solve_quadratic(&a, &b, &c)?.into_iter().map(Root::Exact));
+            } else {
+                roots.extend(part.isolate_real_roots()?.into_iter().map(Root::Isolated));
+            }
+        }
+    }
+    roots.sort_by(|a, b| a.to_real().to_f64().partial_cmp(&b.to_real().to_f64()).unwrap_or(Ordering::Equal));
+    Some(roots)
+}
+
+// A quartic without rational roots can still be the product of two
+// rational quadratics. The real roots of such a quadratic factor are
+// a pair of the approximated real roots. For an integer quartic with
+// leading coefficient l, the multiple l x^2 - l s x + l p of the factor
+// has integer coefficients for their sum s and product p, because the
+// leading coefficient of the integer factor divides l. The candidates
+// are rounded to integers and checked by exact division.
+fn quadratic_factors(p: &Poly) -> Option<Vec<Poly>> {
+    if p.degree() == 0 {
+        return Some(Vec::new());
+    }
+    if p.degree() != 4 {
+        return Some(vec![p.clone()]);
+    }
+    let integer = p.to_integer()?;
+    let leading = *integer.leading().numer();
+    let roots = integer.isolate_real_roots()?.iter().map(IsolatedRoot::to_f64).collect::<Vec<_>>();
+    for (i, first) in roots.iter().enumerate() {
+        for second in roots.iter().skip(i + 1) {
+            let b = (-f64::from(leading) * (first + second)).round();
+            let c = (f64::from(leading) * first * second).round();
+            if b.abs() > i32::MAX as f64 || c.abs() > i32::MAX as f64 {
+                continue;
+            }
+            let factor = Poly::new(vec![Rational32::from(c as i32), Rational32::from(b as i32), Rational32::from(leading)]);
+            let (quotient, remainder) = integer.checked_div_rem(&factor)?;
+            if remainder.is_zero() {
+                return Some(vec![factor, quotient]);
+            }
+        }
+    }
+    Some(vec![p.clone()])
+}
+
+fn trim(coefficients: &mut Vec<Surd>) {
+    while coefficients.last().is_some_and(Surd::is_zero) {
+        coefficients.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_traits::ToPrimitive;
+    use crate::conic::parse_conic;
+
+    fn line(input: &str) -> Line {
+        let c = parse_conic(input);
+        Line::from_rational(c.d, c.e, c.f)
+    }
+
+    fn points(result: LineIntersection) -> Vec<String> {
+        match result {
+            LineIntersection::Points(points) => points.iter()
+                .map(|(x, y)| format!("({}, {})", x.to_string(), y.to_string()))
+                .collect(),
+            other => panic!("Expected points, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn secant() {
+        let result = intersect_line(&line("y = x + 1"), &parse_conic("x^2 + y^2 = 25")).unwrap();
+        assert_eq!(points(result), vec!["(3, 4)", "(-4, -3)"]);
+        let result = intersect_line(&line("y = x"), &parse_conic("x^2 + y^2 = 1")).unwrap();
+        assert_eq!(points(result), vec!["(1/2*sqrt(2), 1/2*sqrt(2))", "(-1/2*sqrt(2), -1/2*sqrt(2))"]);
+    }
+
+    #[test]
+    fn tangent() {
+        let result = intersect_line(&line("y = 5"), &parse_conic("x^2 + y^2 = 25")).unwrap();
+        let five = Surd::from_rational(Rational32::from(5));
+        assert_eq!(result, LineIntersection::Tangent((Surd::zero(), five)));
+    }
+
+    #[test]
+    fn single_crossing() {
+        let result = intersect_line(&line("x = 2"), &parse_conic("y = x^2")).unwrap();
+        assert_eq!(points(result), vec!["(2, 4)"]);
+        let result = intersect_line(&line("y = x + 1"), &parse_conic("x^2 - y^2 = 1")).unwrap();
+        assert_eq!(points(result), vec!["(-1, 0)"]);
+    }
+
+    #[test]
+    fn no_intersection() {
+        assert_eq!(points(intersect_line(&line("y = 0"), &parse_conic("x*y = 1")).unwrap()), Vec::<String>::new());
+        assert_eq!(points(intersect_line(&line("y = 6"), &parse_conic("x^2 + y^2 = 25")).unwrap()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn contained() {
+        assert_eq!(intersect_line(&line("y = x"), &parse_conic("x^2 - y^2 = 0")), Some(LineIntersection::Contained));
+        assert_eq!(points(intersect_line(&line("y = -x + 1"), &parse_conic("x^2 - y^2 = 0")).unwrap()), vec!["(1/2, 1/2)"]);
+    }
+
+    #[test]
+    fn not_a_line() {
+        assert_eq!(intersect_line(&Line::from_rational(Rational32::from(0), Rational32::from(0), Rational32::from(1)), &parse_conic("x = y")), None);
+    }
+
+    fn conic_points(first: &str, second: &str) -> Vec<String> {
+        match intersect_conics(&parse_conic(first), &parse_conic(second)).unwrap() {
+            ConicIntersection::Points(points) => points.iter()
+                .map(|p| format!("({}, {})", p.x.to_real().to_string(), p.y.to_string()))
+                .collect(),
+            other => panic!("Expected points, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn circles() {
+        assert_eq!(conic_points("x^2 + y^2 = 25", "(x - 6)^2 + y^2 = 25"), vec!["(3, -4)", "(3, 4)"]);
+        assert_eq!(conic_points("x^2 + y^2 = 1", "(x - 2)^2 + y^2 = 1"), vec!["(1, 0)"]);
+        assert_eq!(conic_points("x^2 + y^2 = 1", "x^2 + y^2 = 4"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rational_root_of_cubic() {
+        // x^3 - x - 6 = (x - 2)(x^2 + 2x + 3)
+        assert_eq!(conic_points("y = x^2", "x*y = x + 6"), vec!["(2, 4)"]);
+        // 6x^3 - 7x^2 - x + 2 = (x - 1)(2x + 1)(3x - 2)
+        assert_eq!(conic_points("y = x^2", "6*x*y = 7*y + x - 2"), vec!["(-1/2, 1/4)", "(2/3, 4/9)", "(1, 1)"]);
+    }
+
+    #[test]
+    fn quadratic_factors_of_quartic() {
+        assert_eq!(conic_points("y = x^2", "y^2 - 5*y + 6 = 0"),
+            vec!["(-sqrt(3), 3)", "(-sqrt(2), 2)", "(sqrt(2), 2)", "(sqrt(3), 3)"]);
+        assert_eq!(conic_points("y = x^2", "y^2 - y - 2 = 0"), vec!["(-sqrt(2), 2)", "(sqrt(2), 2)"]);
+    }
+
+    #[test]
+    fn isolated_root() {
+        let result = intersect_conics(&parse_conic("y = x^2"), &parse_conic("x*y = 2")).unwrap();
+        let point = match result {
+            ConicIntersection::Points(points) if points.len() == 1 => points[0].clone(),
+            other => panic!("Expected one point, got {:?}", other)
+        };
+        let cube_root = 2f64.powf(1.0 / 3.0);
+        assert!((point.x.to_real().to_f64() - cube_root).abs() < 1e-12);
+        assert!((point.y.to_f64() - cube_root * cube_root).abs() < 1e-9);
+        let mut root = match point.x {
+            Root::Isolated(root) => root,
+            other => panic!("Expected an isolated root, got {:?}", other)
+        };
+        let width = Rational32::new(1, 1000);
+        root.refine(width).unwrap();
+        assert!(root.upper() - root.lower() <= width);
+        let cube = |r: Rational32| r.to_f64().unwrap().powi(3);
+        assert!(cube(root.lower()) < 2.0 && cube(root.upper()) > 2.0);
+    }
+
+    #[test]
+    fn leading_coefficients_vanish() {
+        // The resultant -x^2 - x also vanishes at x = 0, where x*y = 1 has no point.
+        assert_eq!(conic_points("x*y = 1", "x*y = x + 2"), vec!["(-1, -1)"]);
+        assert_eq!(conic_points("x^2 = 1", "x^2 + y^2 = 2"), vec!["(-1, -1)", "(-1, 1)", "(1, -1)", "(1, 1)"]);
+        assert_eq!(conic_points("x^2 = 1", "x^2 = 4"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn common_component() {
+        assert_eq!(intersect_conics(&parse_conic("x^2 - y^2 = 0"), &parse_conic("x*y - y^2 = 0")), Some(ConicIntersection::CommonComponent));
+        assert_eq!(intersect_conics(&parse_conic("x^2 + y^2 = 1"), &parse_conic("2*x^2 + 2*y^2 = 2")), Some(ConicIntersection::CommonComponent));
+        assert_eq!(intersect_conics(&parse_conic("x^2 = 1"), &parse_conic("x^2 + x*y - x - y = 0")), Some(ConicIntersection::CommonComponent));
+    }
+}
@@ -0,0 +1,340 @@
+// Render expressions, equations and results of sqrt::sqrt as LaTeX.
+//
+// Unlike to_string(), only the parentheses that are needed to
+// preserve the structure of the tree are written. Which sign is
+// used for multiplication and how fractions are written can be
+// configured through LatexOptions.
+
+use ::num_rational::Rational32;
+use num_traits::Signed;
+use num_traits::Zero;
+use num_traits::One;
+use crate::expression::Expression;
+use crate::expression::Equation;
+use crate::expression::Operator;
+use crate::expression::PRECEDENCE_SUM;
+use crate::expression::PRECEDENCE_PRODUCT;
+use crate::expression::PRECEDENCE_NEGATION;
+use crate::expression::PRECEDENCE_ATOM;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MultiplicationSign {
+    // 2x, but 2 \cdot 3 when omitting the sign would be ambiguous
+    Implicit,
+    Cdot,
+    Times
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FractionStyle {
+    Frac,
+    Dfrac,
+    Slash
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatexOptions {
+    pub multiplication: MultiplicationSign,
+    pub fraction: FractionStyle
+}
+
+impl Default for LatexOptions {
+    fn default() -> LatexOptions {
+        LatexOptions {
+            multiplication: MultiplicationSign::Implicit,
+            fraction: FractionStyle::Frac
+        }
+    }
+}
+
+pub fn expression_to_latex(e: &Expression, options: &LatexOptions) -> String {
+    match e {
+        Expression::Number(s) => s.to_owned(),
+        Expression::Variable(v) => variable_to_latex(v),
+        Expression::Negation(inner) => {
+            let needs_parens = effective_precedence(inner, options) <= PRECEDENCE_NEGATION;
+            let mut result = String::from("-");
+            result.push_str(&operand_to_latex(inner, needs_parens, options));
+            result
+        },
+        Expression::Composite(c) => {
+            let left = &c.left;
+            let right = &c.right;
+            match c.operator {
+                Operator::PLUS | Operator::MINUS => {
+                    let right_parens = effective_precedence(right, options) <= PRECEDENCE_SUM
+                        || is_negation(right);
+                    let mut result = expression_to_latex(left, options);
+                    result.push(' ');
+                    result.push_str(&c.operator.to_string());
+                    result.push(' ');
+                    result.push_str(&operand_to_latex(right, right_parens, options));
+                    result
+                },
+                Operator::MULT => {
+                    let left_parens = effective_precedence(left, options) < PRECEDENCE_PRODUCT;
+                    let right_parens = effective_precedence(right, options) < PRECEDENCE_PRODUCT
+                        || is_negation(right);
+                    let left_latex = operand_to_latex(left, left_parens, options);
+                    let right_latex = operand_to_latex(right, right_parens, options);
+                    let implicit_allowed = !is_slash_division(left, options) && starts_implicitly(&right_latex);
+                    let mut result = left_latex;
+                    result.push_str(multiplication_sign(options, implicit_allowed));
+                    result.push_str(&right_latex);
+                    result
+                },
+                Operator::DIV => {
+                    match options.fraction {
+                        FractionStyle::Slash => {
+                            let left_parens = effective_precedence(left, options) < PRECEDENCE_PRODUCT;
+                            let right_parens = effective_precedence(right, options) <= PRECEDENCE_PRODUCT
+                                || is_negation(right);
+                            let mut result = operand_to_latex(left, left_parens, options);
+                            result.push_str(" / ");
+                            result.push_str(&operand_to_latex(right, right_parens, options));
+                            result
+                        },
+                        _ => fraction_to_latex(
+                            &expression_to_latex(left, options),
+                            &expression_to_latex(right, options),
+                            options)
+                    }
+                },
+                Operator::POW => {
+                    let base_parens = !matches!(**left, Expression::Number(_) | Expression::Variable(_));
+                    let mut result = operand_to_latex(left, base_parens, options);
+                    result.push_str("^{");
+                    result.push_str(&expression_to_latex(right, options));
+                    result.push('}');
+                    result
+                }
+            }
+        },
+        Expression::Call(c) => {
+            let arguments: Vec<String> = c.arguments.iter().map(|a| expression_to_latex(a, options)).collect();
+            match (c.name.as_str(), c.arguments.as_slice()) {
+                ("sqrt", [_]) => format!("\\sqrt{{{}}}", arguments[0]),
+                ("abs", [_]) => format!("\\left|{}\\right|", arguments[0]),
+                ("sq", [u]) => expression_to_latex(
+                    &Expression::composite(u.clone(), Operator::POW, Expression::number(2)),
+                    options),
+                _ => format!("\\operatorname{{{}}}{}", c.name, parenthesize(&arguments.join(", ")))
+            }
+        }
+    }
+}
+
+pub fn equation_to_latex(e: &Equation, options: &LatexOptions) -> String {
+    let mut result = expression_to_latex(&e.left, options);
+    result.push_str(" = ");
+    result.push_str(&expression_to_latex(&e.right, options));
+    result
+}
+
+// Render a result (q, r) of sqrt::sqrt, meaning q times the root of r.
+pub fn surd_to_latex(surd: (Rational32, u32), options: &LatexOptions) -> String {
+    let (coefficient, radicand) = surd;
+    if coefficient.is_zero() || radicand == 1 {
+        return rational_to_latex(coefficient, options);
+    }
+    let mut result = String::new();
+    if coefficient.is_negative() {
+        result.push('-');
+    }
+    let magnitude = coefficient.abs();
+    if !magnitude.is_one() {
+        if magnitude.is_integer() {
+            result.push_str(&magnitude.numer().to_string());
+        } else {
+            let fraction = rational_to_latex(magnitude, options);
+            if options.fraction == FractionStyle::Slash {
+                result.push_str(&parenthesize(&fraction));
+            } else {
+                result.push_str(&fraction);
+            }
+        }
+        result.push_str(multiplication_sign(options, true));
+    }
+    result.push_str("\\sqrt{");
+    result.push_str(&radicand.to_string());
+    result.push('}');
+    result
+}
+
+pub fn rational_to_latex(r: Rational32, options: &LatexOptions) -> String {
+    let mut result = String::new();
+    if r.is_negative() {
+        result.push('-');
+    }
+    let numer = r.numer().abs().to_string();
+    if r.is_integer() {
+        result.push_str(&numer);
+    } else {
+        result.push_str(&fraction_to_latex(&numer, &r.denom().to_string(), options));
+    }
+    result
+}
+
+fn fraction_to_latex(numer: &str, denom: &str, options: &LatexOptions) -> String {
+    match options.fraction {
+        FractionStyle::Frac => format!("\\frac{{{}}}{{{}}}", numer, denom),
+        FractionStyle::Dfrac => format!("\\dfrac{{{}}}{{{}}}", numer, denom),
+        FractionStyle::Slash => format!("{}/{}", numer, denom)
+    }
+}
+
+fn variable_to_latex(v: &str) -> String {
+    if v.chars().count() == 1 {
+        v.to_owned()
+    } else {
+        format!("\\mathit{{{}}}", v)
+    }
+}
+
+fn operand_to_latex(e: &Expression, needs_parens: bool, options: &LatexOptions) -> String {
+    let latex = expression_to_latex(e, options);
+    if needs_parens {
+        parenthesize(&latex)
+    } else {
+        latex
+    }
+}
+
+fn parenthesize(latex: &str) -> String {
+    format!("\\left({}\\right)", latex)
+}
+
+// A division rendered with \frac is as compact as a number.
+fn effective_precedence(e: &Expression, options: &LatexOptions) -> u32 {
+    match e {
+        Expression::Composite(c) if matches!(c.operator, Operator::DIV) && options.fraction != FractionStyle::Slash => PRECEDENCE_ATOM,
+        _ => e.precedence()
+    }
+}
+
+fn is_negation(e: &Expression) -> bool {
+    matches!(e, Expression::Negation(_))
+}
+
+fn is_slash_division(e: &Expression, options: &LatexOptions) -> bool {
+    match e {
+        Expression::Composite(c) => matches!(c.operator, Operator::DIV) && options.fraction == FractionStyle::Slash,
+        _ => false
+    }
+}
+
+// Juxtaposition only reads as multiplication when the right factor
+// starts with a letter, a root, an absolute value, a function name or
+// a parenthesis. Otherwise 2 3 or
+// 2 \frac{1}{2} would be read as a number or a mixed fraction.
+fn starts_implicitly(right: &str) -> bool {
+    match right.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => true,
+        Some('\\') => ["\\left(", "\\left|", "\\sqrt", "\\mathit", "\\operatorname"].iter().any(|prefix| right.starts_with(prefix)),
+        _ => false
+    }
+}
+
+fn multiplication_sign(options: &LatexOptions, implicit_allowed: bool) -> &'static str {
+    match options.multiplication {
+        MultiplicationSign::Implicit if implicit_allowed => "",
+        MultiplicationSign::Implicit | MultiplicationSign::Cdot => " \\cdot ",
+        MultiplicationSign::Times => " \\times "
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
+    use crate::sqrt::sqrt;
+
+    fn latex(input: &str) -> String {
+        expression_to_latex(&ExprParser::new().parse(input).unwrap(), &LatexOptions::default())
+    }
+
+    fn latex_with(input: &str, multiplication: MultiplicationSign, fraction: FractionStyle) -> String {
+        let options = LatexOptions{multiplication, fraction};
+        expression_to_latex(&ExprParser::new().parse(input).unwrap(), &options)
+    }
+
+    #[test]
+    fn coefficient_times_power() {
+        assert_eq!(latex("3/4*x^2 + 2"), "\\frac{3}{4}x^{2} + 2");
+    }
+
+    #[test]
+    fn implicit_multiplication_needs_sign_between_numbers() {
+        assert_eq!(latex("2*3*x"), "2 \\cdot 3x");
+        assert_eq!(latex("2*(1/2)"), "2 \\cdot \\frac{1}{2}");
+    }
+
+    #[test]
+    fn only_needed_parentheses() {
+        assert_eq!(latex("(x+1)*(x-1)"), "\\left(x + 1\\right)\\left(x - 1\\right)");
+        assert_eq!(latex("x-(y-1)"), "x - \\left(y - 1\\right)");
+        assert_eq!(latex("(x+1)-y"), "x + 1 - y");
+        assert_eq!(latex("x*-y"), "x\\left(-y\\right)");
+    }
+
+    #[test]
+    fn negation_and_power() {
+        assert_eq!(latex("-x^2"), "-x^{2}");
+        assert_eq!(latex("(-x)^2"), "\\left(-x\\right)^{2}");
+        assert_eq!(latex("-(x+y)"), "-\\left(x + y\\right)");
+        assert_eq!(latex("x^(y+1)"), "x^{y + 1}");
+        assert_eq!(latex("(x^2)^3"), "\\left(x^{2}\\right)^{3}");
+    }
+
+    #[test]
+    fn long_variable_names() {
+        assert_eq!(latex("2*ab"), "2\\mathit{ab}");
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(latex("2*sqrt(x^2 + 1)"), "2\\sqrt{x^{2} + 1}");
+        assert_eq!(latex("3*abs(x - 1)"), "3\\left|x - 1\\right|");
+        assert_eq!(latex("sq(x + 1) + sq(y)"), "\\left(x + 1\\right)^{2} + y^{2}");
+        assert_eq!(latex("2*f(x, 1/2)"), "2\\operatorname{f}\\left(x, \\frac{1}{2}\\right)");
+    }
+
+    #[test]
+    fn configured_multiplication_sign() {
+        assert_eq!(latex_with("2*x", MultiplicationSign::Cdot, FractionStyle::Frac), "2 \\cdot x");
+        assert_eq!(latex_with("2*x", MultiplicationSign::Times, FractionStyle::Frac), "2 \\times x");
+    }
+
+    #[test]
+    fn configured_fraction_style() {
+        assert_eq!(latex_with("x/(y+1)", MultiplicationSign::Implicit, FractionStyle::Dfrac), "\\dfrac{x}{y + 1}");
+        assert_eq!(latex_with("x/(y+1)", MultiplicationSign::Implicit, FractionStyle::Slash), "x / \\left(y + 1\\right)");
+        assert_eq!(latex_with("3/4*x", MultiplicationSign::Implicit, FractionStyle::Slash), "3 / 4 \\cdot x");
+    }
+
+    #[test]
+    fn equation() {
+        let e = EquationParser::new().parse("x^2/4 + y^2/9 = 1").unwrap();
+        assert_eq!(equation_to_latex(&e, &LatexOptions::default()), "\\frac{x^{2}}{4} + \\frac{y^{2}}{9} = 1");
+    }
+
+    #[test]
+    fn surd() {
+        let options = LatexOptions::default();
+        assert_eq!(surd_to_latex(sqrt(Rational32::new(12, 1)).unwrap(), &options), "2\\sqrt{3}");
+        assert_eq!(surd_to_latex(sqrt(Rational32::new(27, 16)).unwrap(), &options), "\\frac{3}{4}\\sqrt{3}");
+        assert_eq!(surd_to_latex(sqrt(Rational32::new(64, 25)).unwrap(), &options), "\\frac{8}{5}");
+        assert_eq!(surd_to_latex(sqrt(Rational32::new(2, 1)).unwrap(), &options), "\\sqrt{2}");
+        assert_eq!(surd_to_latex((Rational32::new(-1, 2), 5), &options), "-\\frac{1}{2}\\sqrt{5}");
+        assert_eq!(surd_to_latex((Rational32::zero(), 5), &options), "0");
+    }
+
+    #[test]
+    fn surd_with_options() {
+        let slash = LatexOptions{multiplication: MultiplicationSign::Implicit, fraction: FractionStyle::Slash};
+        assert_eq!(surd_to_latex((Rational32::new(3, 4), 3), &slash), "\\left(3/4\\right)\\sqrt{3}");
+        let cdot = LatexOptions{multiplication: MultiplicationSign::Cdot, fraction: FractionStyle::Dfrac};
+        assert_eq!(surd_to_latex((Rational32::new(3, 4), 3), &cdot), "\\dfrac{3}{4} \\cdot \\sqrt{3}");
+    }
+}
@@ -0,0 +1,298 @@
+// Exact linear algebra over the rationals.
+//
+// Elimination scales each row to integers and runs the fraction-free
+// Bareiss algorithm: after the k-th step every entry below the pivots
+// is a (k+1)x(k+1) minor of the integer matrix, so the division by the
+// previous pivot is exact and the entries grow no more than the
+// determinants do. The arithmetic is checked i64, and everything
+// returns None on overflow.
+
+use ::num_rational::Rational32;
+use ::num_rational::Rational64;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::One;
+use num_traits::Zero;
+use crate::conic::Matrix3;
+use crate::primes::lcm;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    rows: usize,
+    columns: usize,
+    // Row by row.
+    entries: Vec<Rational32>
+}
+
+// The row echelon form of the integer matrix with the row scales, the
+// columns of the pivots and the sign of the row permutation.
+struct Echelon {
+    rows: Vec<Vec<i64>>,
+    scales: Vec<i64>,
+    pivots: Vec<usize>,
+    swaps: usize
+}
+
+impl Matrix {
+    // None if the rows have different lengths. An empty list gives the
+    // 0x0 matrix.
+    pub fn from_rows(rows: Vec<Vec<Rational32>>) -> Option<Matrix> {
+        let columns = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != columns) {
+            return None;
+        }
+        Some(Matrix{rows: rows.len(), columns, entries: rows.concat()})
+    }
+
+    pub fn from_matrix3(m: &Matrix3) -> Matrix {
+        Matrix{rows: 3, columns: 3, entries: m.concat()}
+    }
+
+    pub fn zero(rows: usize, columns: usize) -> Matrix {
+        Matrix{rows, columns, entries: vec![Rational32::zero(); rows * columns]}
+    }
+
+    pub fn identity(size: usize) -> Matrix {
+        let mut result = Matrix::zero(size, size);
+        for i in 0..size {
+            result.entries[i * size + i] = Rational32::one();
+        }
+        result
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Rational32 {
+        self.entries[row * self.columns + column]
+    }
+
+    pub fn row(&self, row: usize) -> &[Rational32] {
+        &self.entries[row * self.columns..(row + 1) * self.columns]
+    }
+
+    // None unless the matrix is 3x3.
+    pub fn to_matrix3(&self) -> Option<Matrix3> {
+        if self.rows != 3 || self.columns != 3 {
+            return None;
+        }
+        Some([0, 1, 2].map(|i| [0, 1, 2].map(|j| self.get(i, j))))
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for j in 0..self.columns {
+            for i in 0..self.rows {
+                entries.push(self.get(i, j));
+            }
+        }
+        Matrix{rows: self.columns, columns: self.rows, entries}
+    }
+
+    // None on overflow and when the sizes do not match.
+    pub fn checked_mul(&self, other: &Matrix) -> Option<Matrix> {
+        if self.columns != other.rows {
+            return None;
+        }
+        let mut result = Matrix::zero(self.rows, other.columns);
+        for i in 0..self.rows {
+            for j in 0..other.columns {
+                let mut sum = Rational32::zero();
+                for k in 0..self.columns {
+                    sum = sum.checked_add(&self.get(i, k).checked_mul(&other.get(k, j))?)?;
+                }
+                result.entries[i * other.columns + j] = sum;
+            }
+        }
+        Some(result)
+    }
+
+    // None on overflow and for matrices that are not square.
+    pub fn determinant(&self) -> Option<Rational32> {
+        if self.rows != self.columns {
+            return None;
+        }
+        let echelon = self.echelon()?;
+        if echelon.pivots.len() < self.rows {
+            return Some(Rational32::zero());
+        }
+        // The last pivot of Bareiss elimination is the determinant of
+        // the integer matrix, which is the product of the row scales
+        // times the determinant.
+        let mut result = Rational64::from(echelon.rows.last().map_or(1, |row| row[self.columns - 1]));
+        if echelon.swaps % 2 == 1 {
+            result = Rational64::zero().checked_sub(&result)?;
+        }
+        for scale in echelon.scales {
+            result = result.checked_div(&Rational64::from(scale))?;
+        }
+        narrow(result)
+    }
+
+    pub fn rank(&self) -> Option<usize> {
+        Some(self.echelon()?.pivots.len())
+    }
+
+    // A basis of the solutions of self * v = 0. Each column without a
+    // pivot gives a basis vector with a 1 there and 0 in the other such
+    // columns.
+    pub fn nullspace(&self) -> Option<Vec<Vec<Rational32>>> {
+        let echelon = self.echelon()?;
+        let mut basis = Vec::new();
+        for free in (0..self.columns).filter(|c| !echelon.pivots.contains(c)) {
+            let mut v = vec![Rational32::zero(); self.columns];
+            v[free] = Rational32::one();
+            back_substitute(&echelon, self.columns, &mut v, |_| Rational64::zero())?;
+            basis.push(v);
+        }
+        Some(basis)
+    }
+
+    // The matrix X with self * X = right. None on overflow, for a
+    // singular or not square matrix and when the sizes do not match.
+    pub fn solve(&self, right: &Matrix) -> Option<Matrix> {
+        if self.rows != self.columns || right.rows != self.rows {
+            return None;
+        }
+        let n = self.columns;
+        let augmented = Matrix::from_rows((0..n).map(|i| [self.row(i), right.row(i)].concat()).collect())?;
+        let echelon = augmented.echelon()?;
+        if echelon.pivots.iter().take(n).copied().ne(0..n) {
+            return None;
+        }
+        let mut result = Matrix::zero(n, right.columns);
+        for j in 0..right.columns {
+            let mut x = vec![Rational32::zero(); n];
+            back_substitute(&echelon, n, &mut x, |k| Rational64::from(echelon.rows[k][n + j]))?;
+            for (i, value) in x.into_iter().enumerate() {
+                result.entries[i * right.columns + j] = value;
+            }
+        }
+        Some(result)
+    }
+
+    // None on overflow and for singular or not square matrices.
+    pub fn inverse(&self) -> Option<Matrix> {
+        self.solve(&Matrix::identity(self.rows))
+    }
+
+    fn echelon(&self) -> Option<Echelon> {
+        let mut rows = Vec::with_capacity(self.rows);
+        let mut scales = Vec::with_capacity(self.rows);
+        for i in 0..self.rows {
+            let row = self.row(i);
+            let scale = row.iter().try_fold(1i64, |l, c| lcm(l, i64::from(*c.denom())))?;
+            rows.push(row.iter().map(|c| i64::from(*c.numer()).checked_mul(scale / i64::from(*c.denom()))).collect::<Option<Vec<_>>>()?);
+            scales.push(scale);
+        }
+        let mut pivots = Vec::new();
+        let mut swaps = 0;
+        let mut previous: i64 = 1;
+        for column in 0..self.columns {
+            let rank = pivots.len();
+            let Some(pivot) = (rank..self.rows).find(|&i| rows[i][column] != 0) else {
+                continue;
+            };
+            if pivot != rank {
+                rows.swap(rank, pivot);
+                scales.swap(rank, pivot);
+                swaps += 1;
+            }
+            let (top, bottom) = rows.split_at_mut(rank + 1);
+            let pivot_row = &top[rank];
+            for row in bottom.iter_mut() {
+                let factor = row[column];
+                for (entry, p) in row.iter_mut().zip(pivot_row).skip(column) {
+                    let minor = pivot_row[column].checked_mul(*entry)?.checked_sub(factor.checked_mul(*p)?)?;
+                    *entry = minor.checked_div(previous)?;
+                }
+            }
+            previous = pivot_row[column];
+            pivots.push(column);
+        }
+        Some(Echelon{rows, scales, pivots, swaps})
+    }
+}
+
+// Solves the echelon rows for the pivot variables among the first
+// `unknowns` columns, from the bottom up, with the values already in x
+// for the others and `right` giving the right side of each row.
+fn back_substitute(echelon: &Echelon, unknowns: usize, x: &mut [Rational32], right: impl Fn(usize) -> Rational64) -> Option<()> {
+    for (k, pivot) in echelon.pivots.iter().enumerate().rev() {
+        let row = &echelon.rows[k];
+        let mut sum = right(k);
+        for j in pivot + 1..unknowns {
+            let value = Rational64::new(i64::from(*x[j].numer()), i64::from(*x[j].denom()));
+            sum = sum.checked_sub(&value.checked_mul(&Rational64::from(row[j]))?)?;
+        }
+        x[*pivot] = narrow(sum.checked_div(&Rational64::from(row[*pivot]))?)?;
+    }
+    Some(())
+}
+
+fn narrow(r: Rational64) -> Option<Rational32> {
+    Some(Rational32::new_raw(i32::try_from(*r.numer()).ok()?, i32::try_from(*r.denom()).ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn matrix(rows: &[&[i32]]) -> Matrix {
+        Matrix::from_rows(rows.iter().map(|row| row.iter().map(|n| Rational32::from(*n)).collect()).collect()).unwrap()
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(matrix(&[&[2, 0, 1], &[1, 3, 2], &[1, 1, 2]]).determinant(), Some(Rational32::from(6)));
+        assert_eq!(matrix(&[&[0, 0, 1], &[0, 2, 0], &[3, 0, 0]]).determinant(), Some(Rational32::from(-6)));
+        assert_eq!(matrix(&[&[0, 1], &[1, 0]]).determinant(), Some(Rational32::from(-1)));
+        assert_eq!(matrix(&[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]).determinant(), Some(Rational32::zero()));
+        let half = Matrix::from_rows(vec![
+            vec![Rational32::new(1, 2), Rational32::new(1, 3)],
+            vec![Rational32::new(1, 4), Rational32::new(1, 5)]]).unwrap();
+        assert_eq!(half.determinant(), Some(Rational32::new(1, 60)));
+        assert_eq!(matrix(&[&[1, 2]]).determinant(), None);
+        assert_eq!(Matrix::identity(0).determinant(), Some(Rational32::from(1)));
+    }
+
+    #[test]
+    fn rank_and_nullspace() {
+        let m = matrix(&[&[1, 2, 3], &[2, 4, 6], &[1, 0, 1]]);
+        assert_eq!(m.rank(), Some(2));
+        let basis = m.nullspace().unwrap();
+        assert_eq!(basis, vec![vec![Rational32::from(-1), Rational32::from(-1), Rational32::from(1)]]);
+        let zero = Matrix::zero(2, 2).checked_mul(&Matrix::identity(2)).unwrap();
+        assert_eq!(zero.rank(), Some(0));
+        assert_eq!(zero.nullspace().unwrap().len(), 2);
+        assert_eq!(Matrix::from_rows(vec![vec![Rational32::from(1)], vec![]]), None);
+    }
+
+    #[test]
+    fn inverse() {
+        assert_eq!(matrix(&[&[2, 1], &[1, 1]]).inverse(), Some(matrix(&[&[1, -1], &[-1, 2]])));
+        let m = matrix(&[&[2, 0, 1], &[1, 3, 2], &[1, 1, 2]]);
+        let inverse = m.inverse().unwrap();
+        assert_eq!(inverse.get(0, 0), Rational32::new(2, 3));
+        assert_eq!(m.checked_mul(&inverse), Some(Matrix::identity(3)));
+        assert_eq!(inverse.checked_mul(&m), Some(Matrix::identity(3)));
+        assert_eq!(matrix(&[&[1, 2], &[2, 4]]).inverse(), None);
+        let solution = matrix(&[&[1, 1], &[1, -1]]).solve(&matrix(&[&[3], &[1]])).unwrap();
+        assert_eq!(solution, matrix(&[&[2], &[1]]));
+    }
+
+    #[test]
+    fn overflow() {
+        let big = matrix(&[&[i32::MAX, 1], &[1, i32::MAX]]);
+        assert_eq!(big.determinant(), None);
+        assert_eq!(big.rank(), Some(2));
+        assert_eq!(big.transpose(), big);
+    }
+}
@@ -0,0 +1,123 @@
+// Lines a x + b y + c = 0 with coefficients that are quadratic surds.
+//
+// Lines computed from a conic, like asymptotes or the axis of a
+// rotated parabola, have irrational coefficients in general. All three
+// coefficients share one radicand, so the surd arithmetic on them
+// succeeds.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use crate::surd::Surd;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Line {
+    pub a: Surd,
+    pub b: Surd,
+    pub c: Surd
+}
+
+impl Line {
+    pub fn new(a: Surd, b: Surd, c: Surd) -> Line {
+        Line{a, b, c}
+    }
+
+    pub fn from_rational(a: Rational32, b: Rational32, c: Rational32) -> Line {
+        Line::new(Surd::from_rational(a), Surd::from_rational(b), Surd::from_rational(c))
+    }
+
+    // The line through the point in the direction (dx, dy):
+    // dy x - dx y + (dx y0 - dy x0) = 0.
+    pub fn through(point: (Surd, Surd), direction: (Surd, Surd)) -> Option<Line> {
+        let (x, y) = point;
+        let (dx, dy) = direction;
+        let c = dx.checked_mul(&y)?.checked_sub(&dy.checked_mul(&x)?)?;
+        Some(Line::new(dy, dx.checked_neg()?, c))
+    }
+
+    // The same line with the first nonzero coefficient of x and y
+    // equal to 1. None for 0 x + 0 y + c = 0.
+    pub fn normalized(&self) -> Option<Line> {
+        let leading = if self.a.is_zero() { self.b } else { self.a };
+        Some(Line::new(
+            self.a.checked_div(&leading)?,
+            self.b.checked_div(&leading)?,
+            self.c.checked_div(&leading)?))
+    }
+
+    // a x + b y + c, which is zero exactly on the line.
+    pub fn evaluate(&self, x: &Surd, y: &Surd) -> Option<Surd> {
+        self.a.checked_mul(x)?.checked_add(&self.b.checked_mul(y)?)?.checked_add(&self.c)
+    }
+
+    // The coefficients as rationals, if none of them is irrational.
+    pub fn to_rational(self) -> Option<(Rational32, Rational32, Rational32)> {
+        Some((self.a.to_rational()?, self.b.to_rational()?, self.c.to_rational()?))
+    }
+}
+
+// Irrational coefficients with a rational part are parenthesized, as
+// in (1 + sqrt(2))*x - y = 0.
+impl ToString for Line {
+    fn to_string(&self) -> String {
+        let terms = [(self.a, "x"), (self.b, "y"), (self.c, "")];
+        let mut result = String::new();
+        for (c, name) in terms.iter() {
+            if c.is_zero() {
+                continue;
+            }
+            let negative = c.signum() == Ordering::Less;
+            let magnitude = if negative { c.checked_neg().unwrap() } else { *c };
+            if negative {
+                result.push_str(if result.is_empty() { "-" } else { " - " });
+            } else if !result.is_empty() {
+                result.push_str(" + ");
+            }
+            let text = if !magnitude.is_rational() && magnitude.rational() != Rational32::from(0) {
+                format!("({})", magnitude.to_string())
+            } else {
+                magnitude.to_string()
+            };
+            if name.is_empty() {
+                result.push_str(&text);
+            } else if magnitude == Surd::one() {
+                result.push_str(name);
+            } else {
+                result.push_str(&format!("{}*{}", text, name));
+            }
+        }
+        if result.is_empty() {
+            result.push('0');
+        }
+        result.push_str(" = 0");
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn r(n: i32) -> Surd {
+        Surd::from_rational(Rational32::from(n))
+    }
+
+    #[test]
+    fn through_point() {
+        let line = Line::through((r(1), r(2)), (r(1), r(3))).unwrap();
+        assert_eq!(line.to_string(), "3*x - y - 1 = 0");
+        assert_eq!(line.evaluate(&r(2), &r(5)), Some(r(0)));
+        assert_eq!(line.to_rational(), Some((Rational32::from(3), Rational32::from(-1), Rational32::from(-1))));
+        assert_eq!(line.normalized().unwrap().to_string(), "x - 1/3*y - 1/3 = 0");
+        assert_eq!(Line::through((r(1), r(2)), (r(1), r(0))).unwrap().normalized().unwrap().to_string(), "y - 2 = 0");
+    }
+
+    #[test]
+    fn to_string() {
+        let sqrt2 = Surd::sqrt(Rational32::from(2)).unwrap();
+        let one_plus = sqrt2.checked_add(&r(1)).unwrap();
+        assert_eq!(Line::new(one_plus, r(-1), r(0)).to_string(), "(1 + sqrt(2))*x - y = 0");
+        assert_eq!(Line::new(r(1), one_plus.checked_neg().unwrap(), r(0)).to_string(), "x - (1 + sqrt(2))*y = 0");
+        assert_eq!(Line::new(r(0), sqrt2.checked_neg().unwrap(), sqrt2).to_string(), "-sqrt(2)*y + sqrt(2) = 0");
+        assert_eq!(Line::from_rational(Rational32::new(1, 2), Rational32::from(0), Rational32::from(0)).to_string(), "1/2*x = 0");
+    }
+}
@@ -7,6 +7,33 @@ mod primes;
 mod expression;
 mod parser;
 mod sqrt;
+mod surd;
+mod latex;
+mod mathml;
+mod evaluate;
+mod simplify;
+mod derivative;
+mod visit;
+mod arena;
+mod canonical;
+mod polynomial;
+mod grading;
+mod script;
+mod conic;
+mod system;
+mod standard_form;
+mod line;
+mod properties;
+mod quadratic;
+mod poly;
+mod intersection;
+mod tangent;
+mod polar;
+mod transform;
+mod fit;
+mod linalg;
+mod pencil;
+mod rational_points;
 
 pub fn main() {
     println!("{}", lang::TermParser::new().parse("(5)").is_ok());
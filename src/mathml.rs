@@ -0,0 +1,293 @@
+// Render expressions, equations and results of sqrt::sqrt as
+// presentation MathML.
+//
+// Every function returns a complete <math> element. As with the
+// LaTeX output, only the parentheses that are needed to preserve the
+// structure of the tree are written. Divisions are always written
+// as <mfrac>, so they never need parentheses themselves.
+
+use ::num_rational::Rational32;
+use num_traits::Signed;
+use num_traits::Zero;
+use num_traits::One;
+use crate::expression::Expression;
+use crate::expression::Equation;
+use crate::expression::Operator;
+use crate::expression::PRECEDENCE_SUM;
+use crate::expression::PRECEDENCE_PRODUCT;
+use crate::expression::PRECEDENCE_NEGATION;
+use crate::expression::PRECEDENCE_ATOM;
+
+const MATH_OPEN: &str = "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">";
+const MATH_CLOSE: &str = "</math>";
+const MINUS_SIGN: &str = "<mo>&#x2212;</mo>";
+const INVISIBLE_TIMES: &str = "<mo>&#x2062;</mo>";
+const DOT_OPERATOR: &str = "<mo>&#x22C5;</mo>";
+const FUNCTION_APPLICATION: &str = "<mo>&#x2061;</mo>";
+
+pub fn expression_to_mathml(e: &Expression) -> String {
+    wrap_math(&render(e))
+}
+
+pub fn equation_to_mathml(e: &Equation) -> String {
+    let mut content = render(&e.left);
+    content.push_str("<mo>=</mo>");
+    content.push_str(&render(&e.right));
+    wrap_math(&content)
+}
+
+// Render a result (q, r) of sqrt::sqrt, meaning q times the root of r.
+pub fn surd_to_mathml(surd: (Rational32, u32)) -> String {
+    let (coefficient, radicand) = surd;
+    if coefficient.is_zero() || radicand == 1 {
+        return wrap_math(&render_rational(coefficient));
+    }
+    let mut content = String::new();
+    if coefficient.is_negative() {
+        content.push_str(MINUS_SIGN);
+    }
+    let magnitude = coefficient.abs();
+    if !magnitude.is_one() {
+        content.push_str(&render_rational(magnitude));
+        content.push_str(INVISIBLE_TIMES);
+    }
+    content.push_str("<msqrt><mn>");
+    content.push_str(&radicand.to_string());
+    content.push_str("</mn></msqrt>");
+    wrap_math(&content)
+}
+
+fn wrap_math(content: &str) -> String {
+    let mut result = String::from(MATH_OPEN);
+    result.push_str("<mrow>");
+    result.push_str(content);
+    result.push_str("</mrow>");
+    result.push_str(MATH_CLOSE);
+    result
+}
+
+fn render(e: &Expression) -> String {
+    match e {
+        Expression::Number(s) => format!("<mn>{}</mn>", s),
+        Expression::Variable(v) => format!("<mi>{}</mi>", v),
+        Expression::Negation(inner) => {
+            let mut result = String::from(MINUS_SIGN);
+            result.push_str(&render_operand(inner, effective_precedence(inner) <= PRECEDENCE_NEGATION));
+            result
+        },
+        Expression::Composite(c) => {
+            let left = &c.left;
+            let right = &c.right;
+            match c.operator {
+                Operator::PLUS | Operator::MINUS => {
+                    let right_parens = effective_precedence(right) <= PRECEDENCE_SUM || is_negation(right);
+                    let mut result = render(left);
+                    if matches!(c.operator, Operator::PLUS) {
+                        result.push_str("<mo>+</mo>");
+                    } else {
+                        result.push_str(MINUS_SIGN);
+                    }
+                    result.push_str(&render_operand(right, right_parens));
+                    result
+                },
+                Operator::MULT => {
+                    let left_parens = effective_precedence(left) < PRECEDENCE_PRODUCT;
+                    let right_parens = effective_precedence(right) < PRECEDENCE_PRODUCT || is_negation(right);
+                    let mut result = render_operand(left, left_parens);
+                    if right_parens || starts_with_variable(right) {
+                        result.push_str(INVISIBLE_TIMES);
+                    } else {
+                        result.push_str(DOT_OPERATOR);
+                    }
+                    result.push_str(&render_operand(right, right_parens));
+                    result
+                },
+                Operator::DIV => format!("<mfrac>{}{}</mfrac>", render_row(left), render_row(right)),
+                Operator::POW => {
+                    let base_parens = !matches!(**left, Expression::Number(_) | Expression::Variable(_));
+                    let base = if base_parens {
+                        render_operand(left, true)
+                    } else {
+                        render(left)
+                    };
+                    format!("<msup><mrow>{}</mrow>{}</msup>", base, render_row(right))
+                }
+            }
+        },
+        Expression::Call(c) => match (c.name.as_str(), c.arguments.as_slice()) {
+            ("sqrt", [u]) => format!("<msqrt>{}</msqrt>", render(u)),
+            ("abs", [u]) => format!("<mrow><mo>|</mo>{}<mo>|</mo></mrow>", render(u)),
+            ("sq", [u]) => render(&Expression::composite(u.clone(), Operator::POW, Expression::number(2))),
+            _ => {
+                let arguments: Vec<String> = c.arguments.iter().map(render).collect();
+                format!("<mi>{}</mi>{}<mrow><mo>(</mo>{}<mo>)</mo></mrow>",
+                    c.name, FUNCTION_APPLICATION, arguments.join("<mo>,</mo>"))
+            }
+        }
+    }
+}
+
+// Elements like <mfrac> and <msup> take exactly one element per argument.
+fn render_row(e: &Expression) -> String {
+    format!("<mrow>{}</mrow>", render(e))
+}
+
+fn render_operand(e: &Expression, needs_parens: bool) -> String {
+    if needs_parens {
+        format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", render(e))
+    } else {
+        render(e)
+    }
+}
+
+fn render_rational(r: Rational32) -> String {
+    let mut result = String::new();
+    if r.is_negative() {
+        result.push_str(MINUS_SIGN);
+    }
+    let numer = r.numer().abs();
+    if r.is_integer() {
+        result.push_str(&format!("<mn>{}</mn>", numer));
+    } else {
+        result.push_str(&format!("<mfrac><mn>{}</mn><mn>{}</mn></mfrac>", numer, r.denom()));
+    }
+    result
+}
+
+fn effective_precedence(e: &Expression) -> u32 {
+    match e {
+        Expression::Composite(c) if matches!(c.operator, Operator::DIV) => PRECEDENCE_ATOM,
+        _ => e.precedence()
+    }
+}
+
+fn is_negation(e: &Expression) -> bool {
+    matches!(e, Expression::Negation(_))
+}
+
+// Juxtaposition only reads as multiplication when the right factor
+// starts with a letter, a root or a function. Otherwise a dot is
+// written.
+fn starts_with_variable(e: &Expression) -> bool {
+    match e {
+        Expression::Variable(_) | Expression::Call(_) => true,
+        Expression::Composite(c) => matches!(c.operator, Operator::MULT | Operator::POW) && starts_with_variable(&c.left),
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
+    use crate::sqrt::sqrt;
+
+    const MATHML_CORE_ELEMENTS: [&str; 8] = ["math", "mrow", "mi", "mn", "mo", "mfrac", "msup", "msqrt"];
+
+    fn mathml(input: &str) -> String {
+        expression_to_mathml(&ExprParser::new().parse(input).unwrap())
+    }
+
+    fn strip_math(mathml: &str) -> String {
+        let inner = mathml.strip_prefix(MATH_OPEN).unwrap().strip_suffix(MATH_CLOSE).unwrap();
+        inner.strip_prefix("<mrow>").unwrap().strip_suffix("</mrow>").unwrap().to_owned()
+    }
+
+    // Check that tags are balanced, that only MathML core elements
+    // are used, and that <mfrac> and <msup> have two children.
+    fn check_well_formed(xml: &str) {
+        let mut stack: Vec<(String, u32)> = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').unwrap() + start;
+            let tag = &rest[start + 1 .. end];
+            if let Some(name) = tag.strip_prefix('/') {
+                let (open, children) = stack.pop().unwrap();
+                assert_eq!(open, name);
+                if name == "mfrac" || name == "msup" {
+                    assert_eq!(children, 2, "{} needs two children in {}", name, xml);
+                }
+            } else {
+                let name = tag.split(' ').next().unwrap();
+                assert!(MATHML_CORE_ELEMENTS.contains(&name), "unexpected element {}", name);
+                if let Some(parent) = stack.last_mut() {
+                    parent.1 += 1;
+                }
+                stack.push((name.to_owned(), 0));
+            }
+            let text = &rest[end + 1 ..];
+            let text_end = text.find('<').unwrap_or(text.len());
+            assert!(!text[.. text_end].contains('&') || text[.. text_end].starts_with("&#x"));
+            rest = text;
+        }
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn fraction_times_power() {
+        let result = mathml("3/4*x^2 + 2");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<mfrac><mrow><mn>3</mn></mrow><mrow><mn>4</mn></mrow></mfrac><mo>&#x2062;</mo>\
+            <msup><mrow><mi>x</mi></mrow><mrow><mn>2</mn></mrow></msup><mo>+</mo><mn>2</mn>");
+    }
+
+    #[test]
+    fn unary_minus_and_parentheses() {
+        let result = mathml("-(x+1)*y");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<mo>&#x2212;</mo><mrow><mo>(</mo><mi>x</mi><mo>+</mo><mn>1</mn><mo>)</mo></mrow>\
+            <mo>&#x2062;</mo><mi>y</mi>");
+    }
+
+    #[test]
+    fn dot_between_numbers() {
+        let result = mathml("2*3");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result), "<mn>2</mn><mo>&#x22C5;</mo><mn>3</mn>");
+    }
+
+    #[test]
+    fn power_of_composite_base() {
+        let result = mathml("(x-1)^2");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<msup><mrow><mrow><mo>(</mo><mi>x</mi><mo>&#x2212;</mo><mn>1</mn><mo>)</mo></mrow></mrow>\
+            <mrow><mn>2</mn></mrow></msup>");
+    }
+
+    #[test]
+    fn functions() {
+        let result = mathml("2*sqrt(x + 1) - abs(y)");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<mn>2</mn><mo>&#x2062;</mo><msqrt><mi>x</mi><mo>+</mo><mn>1</mn></msqrt>\
+            <mo>&#x2212;</mo><mrow><mo>|</mo><mi>y</mi><mo>|</mo></mrow>");
+        let result = mathml("f(x, 2) + sq(y)");
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<mi>f</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>,</mo><mn>2</mn><mo>)</mo></mrow>\
+            <mo>+</mo><msup><mrow><mi>y</mi></mrow><mrow><mn>2</mn></mrow></msup>");
+    }
+
+    #[test]
+    fn conic_equation() {
+        let e = EquationParser::new().parse("x^2/4 - (y-1)^2/9 = 1").unwrap();
+        let result = equation_to_mathml(&e);
+        check_well_formed(&result);
+        assert!(result.contains("<mo>=</mo><mn>1</mn>"));
+    }
+
+    #[test]
+    fn surd() {
+        let result = surd_to_mathml(sqrt(Rational32::new(27, 16)).unwrap());
+        check_well_formed(&result);
+        assert_eq!(strip_math(&result),
+            "<mfrac><mn>3</mn><mn>4</mn></mfrac><mo>&#x2062;</mo><msqrt><mn>3</mn></msqrt>");
+        assert_eq!(strip_math(&surd_to_mathml((Rational32::new(-1, 1), 2))),
+            "<mo>&#x2212;</mo><msqrt><mn>2</mn></msqrt>");
+        assert_eq!(strip_math(&surd_to_mathml(sqrt(Rational32::new(4, 1)).unwrap())), "<mn>2</mn>");
+    }
+}
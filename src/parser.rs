@@ -2,6 +2,7 @@
 mod test {
     use fail::fail_point;
     use crate::lang::ExprParser;
+    use crate::lang::EquationParser;
     use crate::expression::Expression;
 
     #[test]
@@ -34,6 +35,45 @@ mod test {
         parse_and_expect("2+3*5", "(2 + (3 * 5))");
     }
 
+    #[test]
+    fn parse_variable() {
+        parse_and_expect("2*x+y", "((2 * x) + y)");
+    }
+
+    #[test]
+    fn power_takes_precedence_over_mult() {
+        parse_and_expect("3*x^2", "(3 * (x ^ 2))");
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        parse_and_expect("x^2^3", "(x ^ (2 ^ 3))");
+    }
+
+    #[test]
+    fn unary_minus_binds_weaker_than_power() {
+        parse_and_expect("-x^2", "(-(x ^ 2))");
+    }
+
+    #[test]
+    fn unary_minus_after_binary_operator() {
+        parse_and_expect("5--3*x^-1", "(5 - ((-3) * (x ^ (-1))))");
+    }
+
+    #[test]
+    fn function_calls() {
+        parse_and_expect("2*sqrt(x^2 + 1)", "(2 * sqrt(((x ^ 2) + 1)))");
+        parse_and_expect("-abs(x)^2", "(-(abs(x) ^ 2))");
+        parse_and_expect("f(x, y - 1)", "f(x, (y - 1))");
+        assert!(ExprParser::new().parse("f()").is_err());
+    }
+
+    #[test]
+    fn parse_equation() {
+        let parsed = EquationParser::new().parse("x^2 + y^2 = 25").unwrap();
+        assert_eq!(parsed.to_string(), "((x ^ 2) + (y ^ 2)) = 25");
+    }
+
     fn parse_and_expect(input: &str, expected: &str) {
         let r = ExprParser::new().parse(input);
         if r.is_ok() {
@@ -0,0 +1,250 @@
+// Pencils of conics.
+//
+// The pencil of two conics with matrices M1 and M2 consists of the
+// conics with matrices lambda M1 + mu M2, which all pass through the
+// common points of the two. A member is degenerate when its determinant
+// vanishes, which is a cubic equation in t = lambda / mu. Each real
+// degenerate member is a pair of lines through the common points, so
+// intersecting the conics reduces to intersecting lines with a conic.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedMul;
+use crate::conic::Conic;
+use crate::intersection::Root;
+use crate::intersection::real_roots;
+use crate::line::Line;
+use crate::poly::Poly;
+use crate::surd::Surd;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pencil {
+    first: Conic,
+    second: Conic
+}
+
+// The member t C1 + C2, or C1 itself for t at infinity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parameter {
+    Finite(Root),
+    Infinite
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DegenerateMember {
+    pub parameter: Parameter,
+    // The real lines of the member, one for a double line and none for
+    // a pair of complex lines or the line at infinity. None when the
+    // parameter is not exact and when the coefficients of the lines are
+    // not quadratic surds.
+    pub lines: Option<Vec<Line>>
+}
+
+impl Pencil {
+    pub fn new(first: Conic, second: Conic) -> Pencil {
+        Pencil{first, second}
+    }
+
+    pub fn first(&self) -> Conic {
+        self.first
+    }
+
+    pub fn second(&self) -> Conic {
+        self.second
+    }
+
+    // lambda C1 + mu C2, None on overflow.
+    pub fn member(&self, lambda: Rational32, mu: Rational32) -> Option<Conic> {
+        let combine = |x: Rational32, y: Rational32| lambda.checked_mul(&x)?.checked_add(&mu.checked_mul(&y)?);
+        let (p, q) = (self.first, self.second);
+        Some(Conic::new(
+            combine(p.a, q.a)?,
+            combine(p.b, q.b)?,
+            combine(p.c, q.c)?,
+            combine(p.d, q.d)?,
+            combine(p.e, q.e)?,
+            combine(p.f, q.f)?))
+    }
+
+    // det(t M1 + M2) as a polynomial in t. Its degree is below 3 when C1
+    // is degenerate.
+    pub fn characteristic(&self) -> Option<Poly> {
+        let m1 = self.first.matrix()?;
+        let m2 = self.second.matrix()?;
+        let entry = |i: usize, j: usize| Poly::new(vec![m2[i][j], m1[i][j]]);
+        // Expansion along the first row with cyclic minors.
+        let mut result = Poly::zero();
+        for j in 0..3 {
+            let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+            let minor = entry(1, j1).checked_mul(&entry(2, j2))?.checked_sub(&entry(1, j2).checked_mul(&entry(2, j1))?)?;
+            result = result.checked_add(&entry(0, j).checked_mul(&minor)?)?;
+        }
+        Some(result)
+    }
+
+    // The real degenerate members in increasing order of t, with C1
+    // last if it is degenerate. None on overflow and when every member
+    // is degenerate, which happens when the conics share a line.
+    pub fn degenerate_members(&self) -> Option<Vec<DegenerateMember>> {
+        let characteristic = self.characteristic()?;
+        if characteristic.is_zero() {
+            return None;
+        }
+        let mut members = Vec::new();
+        for root in real_roots(&characteristic)? {
+            let lines = match &root {
+                Root::Exact(t) => self.surd_member(t).and_then(|m| split(&m)),
+                Root::Isolated(_) => None
+            };
+            members.push(DegenerateMember{parameter: Parameter::Finite(root), lines});
+        }
+        if characteristic.degree() < 3 {
+            let lines = self.first.matrix().and_then(|m| split(&m.map(|row| row.map(Surd::from_rational))));
+            members.push(DegenerateMember{parameter: Parameter::Infinite, lines});
+        }
+        Some(members)
+    }
+
+    fn surd_member(&self, t: &Surd) -> Option<[[Surd; 3]; 3]> {
+        let m1 = self.first.matrix()?;
+        let m2 = self.second.matrix()?;
+        let mut result = [[Surd::zero(); 3]; 3];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = t.checked_mul(&Surd::from_rational(m1[i][j]))?.checked_add(&Surd::from_rational(m2[i][j]))?;
+            }
+        }
+        Some(result)
+    }
+}
+
+// The real lines of a degenerate conic. For the line pair l and m the
+// matrix is M = (l m^T + m l^T) / 2 and its adjugate is -p p^T / 4 for
+// their crossing p = l x m. Adding the cross product matrix of p / 2
+// to M leaves the rank one matrix l m^T, whose rows and columns are the
+// two lines. A positive diagonal entry of the adjugate means that p is
+// imaginary and the lines are complex.
+fn split(m: &[[Surd; 3]; 3]) -> Option<Vec<Line>> {
+    let adjugate = adjugate(m)?;
+    let Some(i) = (0..3).find(|&i| !adjugate[i][i].is_zero()) else {
+        // A double line, or the zero matrix.
+        let row = m.iter().find(|row| row.iter().any(|e| !e.is_zero()))?;
+        return Some(finite(vec![Line::new(row[0], row[1], row[2])]));
+    };
+    let square = adjugate[i][i].checked_neg()?;
+    if square.signum() == Ordering::Less {
+        return Some(Vec::new());
+    }
+    let root = square.checked_sqrt()?;
+    let [p0, p1, p2] = [0, 1, 2].map(|k| adjugate[k][i].checked_div(&root));
+    let (p0, p1, p2) = (p0?, p1?, p2?);
+    let zero = Surd::zero();
+    let cross = [
+        [zero, p2.checked_neg()?, p1],
+        [p2, zero, p0.checked_neg()?],
+        [p1.checked_neg()?, p0, zero]
+    ];
+    let mut rank_one = [[Surd::zero(); 3]; 3];
+    for (j, row) in rank_one.iter_mut().enumerate() {
+        for (k, entry) in row.iter_mut().enumerate() {
+            *entry = m[j][k].checked_add(&cross[j][k])?;
+        }
+    }
+    let (j, k) = (0..9).map(|n| (n / 3, n % 3)).find(|&(j, k)| !rank_one[j][k].is_zero())?;
+    let row = rank_one[j];
+    let column = [rank_one[0][k], rank_one[1][k], rank_one[2][k]];
+    Some(finite(vec![Line::new(row[0], row[1], row[2]), Line::new(column[0], column[1], column[2])]))
+}
+
+// Drops the line at infinity and scales the others.
+fn finite(lines: Vec<Line>) -> Vec<Line> {
+    lines.iter().filter_map(Line::normalized).collect()
+}
+
+fn adjugate(m: &[[Surd; 3]; 3]) -> Option<[[Surd; 3]; 3]> {
+    let mut result = [[Surd::zero(); 3]; 3];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            let (j1, j2, i1, i2) = ((j + 1) % 3, (j + 2) % 3, (i + 1) % 3, (i + 2) % 3);
+            *entry = m[j1][i1].checked_mul(&m[j2][i2])?.checked_sub(&m[j1][i2].checked_mul(&m[j2][i1])?)?;
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conic::parse_conic;
+
+    fn pencil(first: &str, second: &str) -> Pencil {
+        Pencil::new(parse_conic(first), parse_conic(second))
+    }
+
+    // The parameter and the sorted lines of each member.
+    fn members(p: &Pencil) -> Vec<(String, Vec<String>)> {
+        p.degenerate_members().unwrap().into_iter().map(|member| {
+            let parameter = match member.parameter {
+                Parameter::Finite(t) => t.to_real().to_string(),
+                Parameter::Infinite => String::from("infinity")
+            };
+            let mut lines = member.lines.map_or(vec![String::from("?")], |lines| lines.iter().map(Line::to_string).collect());
+            lines.sort();
+            (parameter, lines)
+        }).collect()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn four_common_points() {
+        // The circle and the hyperbola meet in (1, 2), (2, 1), (-1, -2)
+        // and (-2, -1), and the three line pairs join them.
+        let p = pencil("x^2 + y^2 = 5", "x*y = 2");
+        assert_eq!(p.characteristic().unwrap().to_string(), "-5*x^3 - 2*x^2 + 5/4*x + 1/2");
+        assert_eq!(members(&p), vec![
+            (String::from("-1/2"), strings(&["x - y + 1 = 0", "x - y - 1 = 0"])),
+            (String::from("-2/5"), strings(&["x - 1/2*y = 0", "x - 2*y = 0"])),
+            (String::from("1/2"), strings(&["x + y + 3 = 0", "x + y - 3 = 0"]))]);
+        assert_eq!(p.member(Rational32::from(1), Rational32::from(2)).unwrap().to_string(), "x^2 + 2*x*y + y^2 - 9 = 0");
+    }
+
+    #[test]
+    fn degenerate_first_conic() {
+        // The lines x = +-1 meet the parabola y = x^2 in (1, 1), (-1, 1).
+        let p = pencil("x^2 = 1", "y = x^2");
+        let result = members(&p);
+        assert_eq!(result.last(), Some(&(String::from("infinity"), strings(&["x + 1 = 0", "x - 1 = 0"]))));
+        assert_eq!(result[0], (String::from("1"), strings(&["y - 1 = 0"])));
+    }
+
+    #[test]
+    fn surd_lines_and_complex_pairs() {
+        // -C1 + C2 is -x^2 - x + 1 = 0, and the other roots are complex.
+        let p = pencil("x^2 + y^2 = 1", "y^2 = x");
+        assert_eq!(members(&p), vec![(String::from("-1"), strings(&["x + (1/2 + 1/2*sqrt(5)) = 0", "x - (-1/2 + 1/2*sqrt(5)) = 0"]))]);
+        // x^2 + y^2 = 0 is a pair of complex lines through the origin.
+        let p = pencil("x^2 + y^2 = 1", "x^2 + y^2 = 0");
+        assert_eq!(members(&p)[0], (String::from("-1"), Vec::new()));
+        assert_eq!(members(&p)[1], (String::from("0"), Vec::new()));
+    }
+
+    #[test]
+    fn surd_parameters() {
+        // The common points are (+-2, 0) and +-(2, 4)/sqrt(5), and two of
+        // the line pairs are parallel lines with surd slopes.
+        let p = pencil("x^2 + y^2 = 4", "y^2 = 2*x*y");
+        assert_eq!(members(&p), vec![
+            (String::from("-1/2 - 1/2*sqrt(5)"), strings(&["x + (-1/2 + 1/2*sqrt(5))*y + 2 = 0", "x + (-1/2 + 1/2*sqrt(5))*y - 2 = 0"])),
+            (String::from("0"), strings(&["x - 1/2*y = 0", "y = 0"])),
+            (String::from("-1/2 + 1/2*sqrt(5)"), strings(&["x - (1/2 + 1/2*sqrt(5))*y + 2 = 0", "x - (1/2 + 1/2*sqrt(5))*y - 2 = 0"]))]);
+    }
+
+    #[test]
+    fn common_line() {
+        assert_eq!(pencil("x*y = 0", "x*(x + y - 1) = 0").degenerate_members(), None);
+    }
+}
@@ -0,0 +1,102 @@
+// Poles and polars with respect to a conic.
+//
+// With the symmetric matrix M of the conic and homogeneous coordinates
+// P = (x, y, 1), the polar of the point P is the line with coefficients
+// M P, and the pole of a line l is the point M^-1 l. The polar of a point
+// on the conic is its tangent there, and the polar of a point outside
+// is the chord through the two points of contact of its tangents.
+
+use crate::conic::Conic;
+use crate::conic::Matrix3;
+use crate::linalg::Matrix;
+use crate::line::Line;
+use crate::surd::Surd;
+
+// None on overflow and when the polar is not a line, which happens for
+// the center of a central conic, whose polar is the line at infinity,
+// and for singular points of degenerate conics.
+pub fn polar(conic: &Conic, point: (Surd, Surd)) -> Option<Line> {
+    let m = conic.matrix()?;
+    let [a, b, c] = multiply(&m, [point.0, point.1, Surd::one()])?;
+    if a.is_zero() && b.is_zero() {
+        return None;
+    }
+    Some(Line::new(a, b, c))
+}
+
+// None on overflow, for degenerate conics and when the pole is at
+// infinity, which happens for lines through the center of a central
+// conic.
+pub fn pole(conic: &Conic, line: &Line) -> Option<(Surd, Surd)> {
+    let inverse = Matrix::from_matrix3(&conic.matrix()?).inverse()?.to_matrix3()?;
+    let [x, y, w] = multiply(&inverse, [line.a, line.b, line.c])?;
+    if w.is_zero() {
+        return None;
+    }
+    Some((x.checked_div(&w)?, y.checked_div(&w)?))
+}
+
+fn multiply(m: &Matrix3, v: [Surd; 3]) -> Option<[Surd; 3]> {
+    let row = |i: usize| -> Option<Surd> {
+        let mut sum = Surd::zero();
+        for (j, x) in v.iter().enumerate() {
+            sum = sum.checked_add(&Surd::from_rational(m[i][j]).checked_mul(x)?)?;
+        }
+        Some(sum)
+    };
+    Some([row(0)?, row(1)?, row(2)?])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::num_rational::Rational32;
+    use crate::conic::parse_conic;
+    use crate::tangent::Tangents;
+    use crate::tangent::tangents_through;
+
+    fn r(n: i32) -> Surd {
+        Surd::from_rational(Rational32::from(n))
+    }
+
+    #[test]
+    fn chord_of_contact() {
+        let circle = parse_conic("x^2 + y^2 = 25");
+        let chord = polar(&circle, (r(5), r(5))).unwrap();
+        assert_eq!(chord.to_string(), "5*x + 5*y - 25 = 0");
+        assert_eq!(pole(&circle, &chord), Some((r(5), r(5))));
+        // The tangents from (1, -3) touch y = x^2 at x = -1 and x = 3.
+        let chord = polar(&parse_conic("y = x^2"), (r(1), r(-3))).unwrap();
+        assert_eq!(chord.to_string(), "-x + 1/2*y - 3/2 = 0");
+        assert_eq!(chord.evaluate(&r(-1), &r(1)), Some(r(0)));
+        assert_eq!(chord.evaluate(&r(3), &r(9)), Some(r(0)));
+    }
+
+    #[test]
+    fn tangent_is_polar() {
+        let ellipse = parse_conic("x^2/4 + y^2 = 2");
+        let polar = polar(&ellipse, (r(2), r(1))).unwrap();
+        assert_eq!(tangents_through(&ellipse, (r(2), r(1))), Some(Tangents::AtPoint(polar)));
+    }
+
+    #[test]
+    fn surd_coordinates() {
+        let circle = parse_conic("x^2 + y^2 = 1");
+        let sqrt2 = Surd::sqrt(Rational32::from(2)).unwrap();
+        assert_eq!(polar(&circle, (sqrt2, r(0))).unwrap().to_string(), "sqrt(2)*x - 1 = 0");
+        let half_sqrt2 = sqrt2.checked_div(&r(2)).unwrap();
+        let tangent = Line::new(r(1), r(1), sqrt2.checked_neg().unwrap());
+        assert_eq!(pole(&circle, &tangent), Some((half_sqrt2, half_sqrt2)));
+    }
+
+    #[test]
+    fn at_infinity() {
+        let circle = parse_conic("(x - 1)^2 + y^2 = 4");
+        assert_eq!(polar(&circle, (r(1), r(0))), None);
+        // A diameter has its pole at infinity.
+        assert_eq!(pole(&circle, &Line::from_rational(Rational32::from(1), Rational32::from(1), Rational32::from(-1))), None);
+        // Degenerate conics have no poles.
+        assert_eq!(pole(&parse_conic("x^2 = y^2"), &Line::from_rational(Rational32::from(1), Rational32::from(0), Rational32::from(-1))), None);
+        assert_eq!(polar(&parse_conic("x^2 = y^2"), (r(0), r(0))), None);
+    }
+}
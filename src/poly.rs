@@ -0,0 +1,637 @@
+// Polynomials in one variable with rational coefficients.
+//
+// The coefficients are stored lowest degree first without trailing
+// zeros, so the zero polynomial has no coefficients and two
+// polynomials are equal exactly when their coefficients are. Real
+// roots are counted and isolated into rational intervals with Sturm
+// sequences.
+//
+// All arithmetic is checked and returns None on overflow.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::One;
+use num_traits::Signed;
+use num_traits::ToPrimitive;
+use num_traits::Zero;
+use crate::evaluate::checked_neg;
+use crate::primes::divisors;
+use crate::primes::gcd;
+use crate::primes::lcm;
+use crate::surd::Surd;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Poly {
+    coefficients: Vec<Rational32>
+}
+
+impl Poly {
+    // The coefficients of 1, x, x^2 and so on.
+    pub fn new(mut coefficients: Vec<Rational32>) -> Poly {
+        while coefficients.last().is_some_and(Rational32::is_zero) {
+            coefficients.pop();
+        }
+        Poly{coefficients}
+    }
+
+    pub fn zero() -> Poly {
+        Poly::new(Vec::new())
+    }
+
+    pub fn constant(r: Rational32) -> Poly {
+        Poly::new(vec![r])
+    }
+
+    pub fn x() -> Poly {
+        Poly::new(vec![Rational32::zero(), Rational32::one()])
+    }
+
+    pub fn coefficients(&self) -> &[Rational32] {
+        &self.coefficients
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    // Zero for the zero polynomial.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn coefficient(&self, i: usize) -> Rational32 {
+        self.coefficients.get(i).copied().unwrap_or_else(Rational32::zero)
+    }
+
+    pub fn leading(&self) -> Rational32 {
+        self.coefficient(self.degree())
+    }
+
+    pub fn checked_add(&self, other: &Poly) -> Option<Poly> {
+        let length = self.coefficients.len().max(other.coefficients.len());
+        Some(Poly::new((0..length)
+            .map(|i| self.coefficient(i).checked_add(&other.coefficient(i)))
+            .collect::<Option<Vec<_>>>()?))
+    }
+
+    pub fn checked_sub(&self, other: &Poly) -> Option<Poly> {
+        self.checked_add(&other.checked_scale(-Rational32::one())?)
+    }
+
+    pub fn checked_scale(&self, r: Rational32) -> Option<Poly> {
+        Some(Poly::new(self.coefficients.iter()
+            .map(|c| c.checked_mul(&r))
+            .collect::<Option<Vec<_>>>()?))
+    }
+
+    pub fn checked_mul(&self, other: &Poly) -> Option<Poly> {
+        if self.is_zero() || other.is_zero() {
+            return Some(Poly::zero());
+        }
+        let mut result = vec![Rational32::zero(); self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                result[i + j] = result[i + j].checked_add(&a.checked_mul(b)?)?;
+            }
+        }
+        Some(Poly::new(result))
+    }
+
+    pub fn checked_pow(&self, exponent: u32) -> Option<Poly> {
+        let mut result = Poly::constant(Rational32::one());
+        for _ in 0..exponent {
+            result = result.checked_mul(self)?;
+        }
+        Some(result)
+    }
+
+    // Quotient and remainder of Euclidean division, None when dividing
+    // by zero.
+    pub fn checked_div_rem(&self, divisor: &Poly) -> Option<(Poly, Poly)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let mut remainder = self.coefficients.clone();
+        let mut quotient = vec![Rational32::zero(); (remainder.len() + 1).saturating_sub(divisor.coefficients.len())];
+        while remainder.len() >= divisor.coefficients.len() {
+            let shift = remainder.len() - divisor.coefficients.len();
+            let factor = remainder.last()?.checked_div(&divisor.leading())?;
+            quotient[shift] = factor;
+            for (i, c) in divisor.coefficients.iter().enumerate() {
+                remainder[shift + i] = remainder[shift + i].checked_sub(&factor.checked_mul(c)?)?;
+            }
+            remainder.pop();
+            while remainder.last().is_some_and(Rational32::is_zero) {
+                remainder.pop();
+            }
+        }
+        Some((Poly::new(quotient), Poly::new(remainder)))
+    }
+
+    // Monic, and zero only when both are zero.
+    pub fn checked_gcd(&self, other: &Poly) -> Option<Poly> {
+        let mut first = self.clone();
+        let mut second = other.clone();
+        while !second.is_zero() {
+            let remainder = first.checked_div_rem(&second)?.1;
+            first = second;
+            second = remainder;
+        }
+        if first.is_zero() {
+            return Some(first);
+        }
+        first.checked_scale(Rational32::one().checked_div(&first.leading())?)
+    }
+
+    pub fn derivative(&self) -> Option<Poly> {
+        Some(Poly::new(self.coefficients.iter().enumerate().skip(1)
+            .map(|(i, c)| c.checked_mul(&Rational32::from(i32::try_from(i).ok()?)))
+            .collect::<Option<Vec<_>>>()?))
+    }
+
+    pub fn evaluate(&self, x: Rational32) -> Option<Rational32> {
+        self.coefficients.iter().rev()
+            .try_fold(Rational32::zero(), |value, c| value.checked_mul(&x)?.checked_add(c))
+    }
+
+    pub fn evaluate_surd(&self, x: &Surd) -> Option<Surd> {
+        self.coefficients.iter().rev()
+            .try_fold(Surd::zero(), |value, c| value.checked_mul(x)?.checked_add(&Surd::from_rational(*c)))
+    }
+
+    pub fn evaluate_f64(&self, x: f64) -> f64 {
+        self.coefficients.iter().rev()
+            .fold(0.0, |value, c| value * x + c.to_f64().unwrap())
+    }
+
+    // The sign of the value at x = n/d. After multiplying with the
+    // common denominator l of the coefficients and d^k for the degree
+    // k, this is the sign of the sum of l c_i n^i d^(k - i), which is
+    // computed with 128 bit integers. This overflows much later than
+    // evaluating with rationals, which matters for the long
+    // denominators of bisection.
+    pub fn sign_at(&self, x: Rational32) -> Option<Ordering> {
+        let mut denominator: i128 = 1;
+        for c in self.coefficients.iter() {
+            denominator = lcm(denominator, i128::from(*c.denom()))?;
+        }
+        let (n, d) = (i128::from(*x.numer()), i128::from(*x.denom()));
+        let mut sum: i128 = 0;
+        for (i, c) in self.coefficients.iter().enumerate() {
+            let scale = denominator / i128::from(*c.denom());
+            let mut term = i128::from(*c.numer()).checked_mul(scale)?;
+            term = term.checked_mul(n.checked_pow(u32::try_from(i).ok()?)?)?;
+            term = term.checked_mul(d.checked_pow(u32::try_from(self.degree() - i).ok()?)?)?;
+            sum = sum.checked_add(term)?;
+        }
+        Some(sum.cmp(&0))
+    }
+
+    // The primitive polynomial with integer coefficients and a positive
+    // leading coefficient that is a rational multiple of this one.
+    pub fn to_integer(&self) -> Option<Poly> {
+        let mut denominator: i32 = 1;
+        for c in self.coefficients.iter() {
+            denominator = lcm(denominator, *c.denom())?;
+        }
+        let scaled = self.checked_scale(Rational32::from(denominator))?;
+        let content = scaled.coefficients.iter().fold(0, |g, c| gcd(g, i128::from(*c.numer())));
+        if content == 0 {
+            return Some(scaled);
+        }
+        let content = i32::try_from(content).ok()?;
+        let signed = if scaled.leading().is_negative() { content.checked_neg()? } else { content };
+        scaled.checked_scale(Rational32::new(1, signed))
+    }
+
+    // Yun's algorithm: the factors of the square-free decomposition with
+    // their multiplicities, so that the polynomial is a constant times
+    // the product of the factors to the power of their multiplicities.
+    // The factors are monic and pairwise coprime.
+    pub fn square_free_factors(&self) -> Option<Vec<(Poly, u32)>> {
+        let mut factors = Vec::new();
+        if self.degree() == 0 {
+            return Some(factors);
+        }
+        let derivative = self.derivative()?;
+        let common = self.checked_gcd(&derivative)?;
+        let mut b = self.checked_div_rem(&common)?.0;
+        let c = derivative.checked_div_rem(&common)?.0;
+        let mut d = c.checked_sub(&b.derivative()?)?;
+        let mut multiplicity = 1;
+        while b.degree() > 0 {
+            let factor = b.checked_gcd(&d)?;
+            b = b.checked_div_rem(&factor)?.0;
+            let c = d.checked_div_rem(&factor)?.0;
+            d = c.checked_sub(&b.derivative()?)?;
+            if factor.degree() > 0 {
+                factors.push((factor, multiplicity));
+            }
+            multiplicity += 1;
+        }
+        Some(factors)
+    }
+
+    // The monic polynomial with the same roots, each of them simple.
+    // Zero for the zero polynomial.
+    pub fn square_free_part(&self) -> Option<Poly> {
+        if self.is_zero() {
+            return Some(Poly::zero());
+        }
+        let common = self.checked_gcd(&self.derivative()?)?;
+        let part = self.checked_div_rem(&common)?.0;
+        part.checked_scale(Rational32::one().checked_div(&part.leading())?)
+    }
+
+    // The rational roots in increasing order, repeated by multiplicity,
+    // and the polynomial with them divided out, which has no rational
+    // roots left. By the rational root theorem a root p/q in lowest terms
+    // of the integer multiple has p dividing the constant and q dividing
+    // the leading coefficient, so the candidates come from the divisors of
+    // both. Zero is divided out first, which makes the constant nonzero.
+    // None for the zero polynomial and on overflow.
+    pub fn rational_roots(&self) -> Option<(Vec<Rational32>, Poly)> {
+        if self.is_zero() {
+            return None;
+        }
+        let mut roots = Vec::new();
+        let zeros = self.coefficients.iter().take_while(|c| c.is_zero()).count();
+        roots.extend(std::iter::repeat_n(Rational32::zero(), zeros));
+        let mut rest = Poly::new(self.coefficients[zeros..].to_vec());
+        if rest.degree() == 0 {
+            return Some((roots, rest));
+        }
+        let integer = rest.to_integer()?;
+        let constant = integer.coefficient(0).to_integer().unsigned_abs();
+        let leading = integer.leading().to_integer().unsigned_abs();
+        let denominators = divisors(leading);
+        for numerator in divisors(constant) {
+            for denominator in denominators.iter() {
+                for sign in [1, -1] {
+                    if rest.degree() == 0 {
+                        break;
+                    }
+                    // The divisor 2^31 of a coefficient i32::MIN does not fit.
+                    let (Ok(numerator), Ok(denominator)) = (i32::try_from(numerator), i32::try_from(*denominator)) else {
+                        continue;
+                    };
+                    let candidate = Rational32::new(sign * numerator, denominator);
+                    // Candidates that are not in lowest terms were tried before.
+                    if *candidate.denom() != denominator {
+                        continue;
+                    }
+                    // A candidate whose sign overflows cannot be checked and is skipped.
+                    while rest.degree() > 0 && rest.sign_at(candidate) == Some(Ordering::Equal) {
+                        let linear = Poly::new(vec![checked_neg(candidate)?, Rational32::one()]);
+                        rest = rest.checked_div_rem(&linear)?.0;
+                        roots.push(candidate);
+                    }
+                }
+            }
+        }
+        roots.sort();
+        Some((roots, rest))
+    }
+
+    // p, p' and the negated remainders of Euclid's algorithm, each
+    // scaled to a leading coefficient of 1 or -1 to keep the numbers
+    // small. Empty for the zero polynomial.
+    pub fn sturm_sequence(&self) -> Option<Vec<Poly>> {
+        if self.is_zero() {
+            return Some(Vec::new());
+        }
+        let normalize = |p: Poly| -> Option<Poly> {
+            p.checked_scale(Rational32::one().checked_div(&p.leading().abs())?)
+        };
+        let mut sequence = vec![normalize(self.clone())?];
+        let mut next = self.derivative()?;
+        while !next.is_zero() {
+            let next_normalized = normalize(next)?;
+            let remainder = sequence.last()?.checked_div_rem(&next_normalized)?.1;
+            sequence.push(next_normalized);
+            next = remainder.checked_scale(-Rational32::one())?;
+        }
+        Some(sequence)
+    }
+
+    // The number of distinct real roots in (lower, upper], by Sturm's
+    // theorem. None for the zero polynomial, which vanishes everywhere.
+    pub fn count_roots(&self, lower: Rational32, upper: Rational32) -> Option<usize> {
+        if self.is_zero() {
+            return None;
+        }
+        if lower >= upper {
+            return Some(0);
+        }
+        let sturm = self.square_free_part()?.sturm_sequence()?;
+        sign_changes(&sturm, lower)?.checked_sub(sign_changes(&sturm, upper)?)
+    }
+
+    // An integer larger than the absolute value of every root, from
+    // Cauchy's bound 1 + max |c_i / c_n|.
+    pub fn root_bound(&self) -> Option<Rational32> {
+        let leading = self.leading();
+        let mut largest = Rational32::zero();
+        for c in self.coefficients.iter().take(self.degree()) {
+            largest = largest.max(c.checked_div(&leading)?.abs());
+        }
+        largest.ceil().checked_add(&Rational32::from(2))
+    }
+
+    // The distinct real roots in (lower, upper] in increasing order,
+    // each in an interval of its own. The intervals are found by
+    // bisection and Sturm's theorem. None for the zero polynomial.
+    pub fn isolate_roots(&self, lower: Rational32, upper: Rational32) -> Option<Vec<IsolatedRoot>> {
+        if self.is_zero() {
+            return None;
+        }
+        let part = self.square_free_part()?;
+        let sturm = part.sturm_sequence()?;
+        let mut intervals = vec![(lower, upper)];
+        let mut result = Vec::new();
+        while let Some((lower, upper)) = intervals.pop() {
+            if lower >= upper {
+                continue;
+            }
+            let count = sign_changes(&sturm, lower)?.checked_sub(sign_changes(&sturm, upper)?)?;
+            match count {
+                0 => {},
+                1 => result.push(IsolatedRoot{polynomial: part.clone(), lower, upper}),
+                _ => {
+                    let middle = lower.checked_add(&upper)?.checked_div(&Rational32::from(2))?;
+                    intervals.push((middle, upper));
+                    intervals.push((lower, middle));
+                }
+            }
+        }
+        Some(result)
+    }
+
+    // All distinct real roots, isolated in (-m, m] for the root bound m.
+    pub fn isolate_real_roots(&self) -> Option<Vec<IsolatedRoot>> {
+        let bound = self.root_bound()?;
+        self.isolate_roots(checked_neg(bound)?, bound)
+    }
+}
+
+// The number of sign changes of the values at x, skipping zeros.
+fn sign_changes(sequence: &[Poly], x: Rational32) -> Option<usize> {
+    let mut changes = 0;
+    let mut previous = Ordering::Equal;
+    for p in sequence {
+        let sign = p.sign_at(x)?;
+        if sign == Ordering::Equal {
+            continue;
+        }
+        if previous != Ordering::Equal && previous != sign {
+            changes += 1;
+        }
+        previous = sign;
+    }
+    Some(changes)
+}
+
+// The only root of a square-free polynomial in (lower, upper].
+#[derive(Clone, Debug, PartialEq)]
+pub struct IsolatedRoot {
+    polynomial: Poly,
+    lower: Rational32,
+    upper: Rational32
+}
+
+impl IsolatedRoot {
+    pub fn polynomial(&self) -> &Poly {
+        &self.polynomial
+    }
+
+    pub fn lower(&self) -> Rational32 {
+        self.lower
+    }
+
+    pub fn upper(&self) -> Rational32 {
+        self.upper
+    }
+
+    // The root if the bisection has found it exactly.
+    pub fn exact(&self) -> Option<Rational32> {
+        if self.lower == self.upper {
+            Some(self.upper)
+        } else {
+            None
+        }
+    }
+
+    // Bisect until the interval is at most width wide. None on
+    // overflow, which leaves a valid but wider interval. The polynomial
+    // changes sign at the simple root, so the root is in the half where
+    // the sign differs from the sign at upper. A rational root found on
+    // the way makes the interval a single point.
+    pub fn refine(&mut self, width: Rational32) -> Option<()> {
+        while self.upper.checked_sub(&self.lower)? > width {
+            let upper_sign = self.polynomial.sign_at(self.upper)?;
+            if upper_sign == Ordering::Equal {
+                self.lower = self.upper;
+                break;
+            }
+            let middle = self.lower.checked_add(&self.upper)?.checked_div(&Rational32::from(2))?;
+            let sign = self.polynomial.sign_at(middle)?;
+            if sign == Ordering::Equal {
+                self.lower = middle;
+                self.upper = middle;
+            } else if sign == upper_sign {
+                self.upper = middle;
+            } else {
+                self.lower = middle;
+            }
+        }
+        Some(())
+    }
+
+    // Continues the bisection in floating point.
+    pub fn to_f64(&self) -> f64 {
+        let mut lower = self.lower.to_f64().unwrap();
+        let mut upper = self.upper.to_f64().unwrap();
+        let upper_positive = self.polynomial.evaluate_f64(upper) > 0.0;
+        for _ in 0..100 {
+            let middle = (lower + upper) / 2.0;
+            if middle <= lower || middle >= upper {
+                break;
+            }
+            if (self.polynomial.evaluate_f64(middle) > 0.0) == upper_positive {
+                upper = middle;
+            } else {
+                lower = middle;
+            }
+        }
+        (lower + upper) / 2.0
+    }
+}
+
+// Written in x with the highest degree first, like x^3 - 1/2*x + 2.
+impl ToString for Poly {
+    fn to_string(&self) -> String {
+        let mut result = String::new();
+        for (i, c) in self.coefficients.iter().enumerate().rev() {
+            if c.is_zero() {
+                continue;
+            }
+            if c.is_negative() {
+                result.push_str(if result.is_empty() { "-" } else { " - " });
+            } else if !result.is_empty() {
+                result.push_str(" + ");
+            }
+            let magnitude = c.abs();
+            let power = match i {
+                0 => String::new(),
+                1 => "x".to_owned(),
+                _ => format!("x^{}", i)
+            };
+            if power.is_empty() {
+                result.push_str(&magnitude.to_string());
+            } else if magnitude.is_one() {
+                result.push_str(&power);
+            } else {
+                result.push_str(&format!("{}*{}", magnitude, power));
+            }
+        }
+        if result.is_empty() {
+            result.push('0');
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn poly(coefficients: &[i32]) -> Poly {
+        Poly::new(coefficients.iter().map(|c| Rational32::from(*c)).collect())
+    }
+
+    #[test]
+    fn arithmetic() {
+        let p = poly(&[1, 1]);
+        let q = poly(&[-1, 1]);
+        assert_eq!(p.checked_mul(&q).unwrap(), poly(&[-1, 0, 1]));
+        assert_eq!(p.checked_add(&q).unwrap(), poly(&[0, 2]));
+        assert_eq!(p.checked_sub(&p).unwrap(), Poly::zero());
+        assert_eq!(p.checked_pow(3).unwrap(), poly(&[1, 3, 3, 1]));
+        assert_eq!(poly(&[1, 3, 3, 1]).derivative().unwrap(), poly(&[3, 6, 3]));
+        assert_eq!(poly(&[2, 0, -1, 1]).to_string(), "x^3 - x^2 + 2");
+        assert_eq!(Poly::new(vec![Rational32::new(-1, 2), Rational32::from(3)]).to_string(), "3*x - 1/2");
+        assert_eq!(Poly::zero().to_string(), "0");
+    }
+
+    #[test]
+    fn division() {
+        // x^3 - 2x + 1 = (x^2 + x - 1)(x - 1)
+        let (quotient, remainder) = poly(&[1, -2, 0, 1]).checked_div_rem(&poly(&[-1, 1])).unwrap();
+        assert_eq!((quotient, remainder), (poly(&[-1, 1, 1]), Poly::zero()));
+        let (quotient, remainder) = poly(&[1, 0, 1]).checked_div_rem(&poly(&[0, 2])).unwrap();
+        assert_eq!(quotient, Poly::new(vec![Rational32::zero(), Rational32::new(1, 2)]));
+        assert_eq!(remainder, poly(&[1]));
+        assert_eq!(poly(&[1]).checked_div_rem(&Poly::zero()), None);
+    }
+
+    #[test]
+    fn gcd() {
+        let p = poly(&[-1, 0, 1]).checked_mul(&poly(&[2, 1])).unwrap();
+        let q = poly(&[1, 2, 1]);
+        assert_eq!(p.checked_gcd(&q).unwrap(), poly(&[1, 1]));
+        assert_eq!(poly(&[0, 2]).checked_gcd(&Poly::zero()).unwrap(), Poly::x());
+        assert_eq!(poly(&[2, 1]).checked_gcd(&poly(&[3, 1])).unwrap(), poly(&[1]));
+    }
+
+    #[test]
+    fn integer_multiple() {
+        // -1/3 x + 1/2 is a multiple of 2x - 3.
+        let p = Poly::new(vec![Rational32::new(1, 2), Rational32::new(-1, 3)]);
+        assert_eq!(p.to_integer().unwrap(), poly(&[-3, 2]));
+        assert_eq!(poly(&[4, 0, 6]).to_integer().unwrap(), poly(&[2, 0, 3]));
+        assert_eq!(p.sign_at(Rational32::from(2)), Some(Ordering::Less));
+        assert_eq!(p.sign_at(Rational32::new(3, 2)), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn square_free() {
+        // (x - 1)^2 (x + 2)
+        let p = poly(&[2, -3, 0, 1]);
+        assert_eq!(p.square_free_factors().unwrap(), vec![(poly(&[2, 1]), 1), (poly(&[-1, 1]), 2)]);
+        assert_eq!(p.square_free_part().unwrap(), poly(&[-2, 1, 1]));
+        // (2x (x^2 - x))^3 = 8 x^6 (x - 1)^3
+        let p = poly(&[0, 2]).checked_mul(&poly(&[0, -1, 1])).unwrap().checked_pow(3).unwrap();
+        assert_eq!(p.square_free_factors().unwrap(), vec![(poly(&[-1, 1]), 3), (Poly::x(), 6)]);
+    }
+
+    #[test]
+    fn rational_roots() {
+        // 2x^3 - 3x^2 - 3x + 2 = (x - 2)(2x - 1)(x + 1)
+        let (roots, rest) = poly(&[2, -3, -3, 2]).rational_roots().unwrap();
+        assert_eq!(roots, vec![Rational32::from(-1), Rational32::new(1, 2), Rational32::from(2)]);
+        assert_eq!(rest.degree(), 0);
+        // x (x - 1)^2 (x^2 + 1), which keeps the irreducible quadratic
+        let p = poly(&[0, 1]).checked_mul(&poly(&[-1, 1]).checked_pow(2).unwrap()).unwrap()
+            .checked_mul(&poly(&[1, 0, 1])).unwrap();
+        let (roots, rest) = p.rational_roots().unwrap();
+        assert_eq!(roots, vec![Rational32::zero(), Rational32::one(), Rational32::one()]);
+        assert_eq!(rest, poly(&[1, 0, 1]));
+        // 1/6 (6x - 5)(3x + 4)(x^2 - 2)
+        let p = poly(&[-5, 6]).checked_mul(&poly(&[4, 3])).unwrap().checked_mul(&poly(&[-2, 0, 1])).unwrap()
+            .checked_scale(Rational32::new(1, 6)).unwrap();
+        let (roots, rest) = p.rational_roots().unwrap();
+        assert_eq!(roots, vec![Rational32::new(-4, 3), Rational32::new(5, 6)]);
+        assert_eq!(rest.square_free_part().unwrap(), poly(&[-2, 0, 1]));
+        assert_eq!(poly(&[-2, 0, 0, 1]).rational_roots().unwrap(), (Vec::new(), poly(&[-2, 0, 0, 1])));
+        assert_eq!(Poly::zero().rational_roots(), None);
+        // The candidates 2^31 and -2^31 do not fit 32 bits.
+        assert_eq!(poly(&[i32::MIN, 0, 1]).rational_roots().unwrap(), (Vec::new(), poly(&[i32::MIN, 0, 1])));
+    }
+
+    #[test]
+    fn counting_roots() {
+        // (x^2 - 2)(x - 3)^2 has the roots -sqrt(2), sqrt(2) and 3.
+        let p = poly(&[-2, 0, 1]).checked_mul(&poly(&[-3, 1]).checked_pow(2).unwrap()).unwrap();
+        let r = Rational32::from;
+        assert_eq!(p.count_roots(r(-10), r(10)), Some(3));
+        assert_eq!(p.count_roots(r(0), r(2)), Some(1));
+        assert_eq!(p.count_roots(r(2), r(3)), Some(1));
+        assert_eq!(p.count_roots(r(3), r(4)), Some(0));
+        assert_eq!(poly(&[1, 0, 1]).count_roots(r(-10), r(10)), Some(0));
+        assert_eq!(Poly::zero().count_roots(r(0), r(1)), None);
+    }
+
+    #[test]
+    fn isolating_roots() {
+        // x^3 - 3x + 1 has three irrational roots near -1.88, 0.35 and 1.53.
+        let p = poly(&[1, -3, 0, 1]);
+        let roots = p.isolate_real_roots().unwrap();
+        assert_eq!(roots.len(), 3);
+        let expected = [-1.879385241571817, 0.34729635533386066, 1.532088886237956];
+        for (root, x) in roots.iter().zip(expected.iter()) {
+            assert!(root.lower() < root.upper());
+            assert!((root.to_f64() - x).abs() < 1e-12);
+        }
+        let mut root = roots[1].clone();
+        let width = Rational32::new(1, 1 << 20);
+        root.refine(width).unwrap();
+        assert!(root.upper() - root.lower() <= width);
+        assert_ne!(p.sign_at(root.lower()), p.sign_at(root.upper()));
+        let only_positive = p.isolate_roots(Rational32::zero(), Rational32::from(1)).unwrap();
+        assert_eq!(only_positive.len(), 1);
+    }
+
+    #[test]
+    fn rational_root_found_by_bisection() {
+        // (2x - 3)(x + 1)
+        let mut roots = poly(&[-3, -1, 2]).isolate_real_roots().unwrap();
+        assert_eq!(roots.len(), 2);
+        roots[1].refine(Rational32::new(1, 100)).unwrap();
+        assert_eq!(roots[1].exact(), Some(Rational32::new(3, 2)));
+        assert!((roots[0].to_f64() + 1.0).abs() < 1e-12);
+    }
+}
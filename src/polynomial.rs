@@ -0,0 +1,347 @@
+// Multivariate polynomials with rational coefficients.
+//
+// A polynomial is a map from monomials to their coefficients. A
+// monomial maps variable names to positive exponents, so x^2 y is
+// {x: 2, y: 1} and the constant monomial is empty. Coefficients that
+// become zero are removed, which makes the representation a normal
+// form: two polynomials are equal exactly when their maps are equal.
+//
+// All arithmetic is checked and returns None on overflow.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::One;
+use num_traits::Zero;
+use crate::expression::Expression;
+use crate::expression::Operator;
+use crate::evaluate::checked_neg;
+use crate::evaluate::checked_pow;
+use crate::evaluate::apply_function;
+use crate::surd::Surd;
+use crate::visit::Fold;
+use crate::visit::fold;
+
+pub type Monomial = BTreeMap<String, u32>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Polynomial {
+    terms: BTreeMap<Monomial, Rational32>
+}
+
+impl Polynomial {
+    pub fn zero() -> Polynomial {
+        Polynomial{terms: BTreeMap::new()}
+    }
+
+    pub fn constant(r: Rational32) -> Polynomial {
+        let mut result = Polynomial::zero();
+        result.add_term(Monomial::new(), r);
+        result
+    }
+
+    pub fn variable(name: &str) -> Polynomial {
+        let mut monomial = Monomial::new();
+        monomial.insert(name.to_owned(), 1);
+        let mut result = Polynomial::zero();
+        result.add_term(monomial, Rational32::one());
+        result
+    }
+
+    // The normal form of an expression. None when the expression
+    // divides by something that is not a nonzero constant, has an
+    // exponent that is not a natural number, applies a function to a
+    // variable or overflows.
+    pub fn from_expression(e: &Expression) -> Option<Polynomial> {
+        fold(e, &mut PolynomialBuilder)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn terms(&self) -> impl Iterator<Item = (&Monomial, &Rational32)> {
+        self.terms.iter()
+    }
+
+    pub fn coefficient(&self, monomial: &Monomial) -> Rational32 {
+        self.terms.get(monomial).copied().unwrap_or_else(Rational32::zero)
+    }
+
+    // The value if the polynomial is constant.
+    pub fn as_constant(&self) -> Option<Rational32> {
+        if self.terms.len() > 1 {
+            return None;
+        }
+        match self.terms.iter().next() {
+            None => Some(Rational32::zero()),
+            Some((monomial, c)) if monomial.is_empty() => Some(*c),
+            _ => None
+        }
+    }
+
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.terms.keys().flat_map(|m| m.keys().cloned()).collect()
+    }
+
+    // Total degree. The zero polynomial has degree 0.
+    pub fn degree(&self) -> u32 {
+        self.terms.keys().map(|m| m.values().sum()).max().unwrap_or(0)
+    }
+
+    pub fn degree_in(&self, variable: &str) -> u32 {
+        self.terms.keys().map(|m| m.get(variable).copied().unwrap_or(0)).max().unwrap_or(0)
+    }
+
+    pub fn checked_add(&self, other: &Polynomial) -> Option<Polynomial> {
+        let mut result = self.clone();
+        for (monomial, c) in other.terms.iter() {
+            result.checked_add_term(monomial.clone(), *c)?;
+        }
+        Some(result)
+    }
+
+    pub fn checked_sub(&self, other: &Polynomial) -> Option<Polynomial> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Polynomial> {
+        self.checked_scale(Rational32::from(-1))
+    }
+
+    pub fn checked_scale(&self, factor: Rational32) -> Option<Polynomial> {
+        let mut result = Polynomial::zero();
+        for (monomial, c) in self.terms.iter() {
+            result.add_term(monomial.clone(), c.checked_mul(&factor)?);
+        }
+        Some(result)
+    }
+
+    pub fn checked_mul(&self, other: &Polynomial) -> Option<Polynomial> {
+        let mut result = Polynomial::zero();
+        for (first, c) in self.terms.iter() {
+            for (second, d) in other.terms.iter() {
+                let mut monomial = first.clone();
+                for (variable, exponent) in second.iter() {
+                    let e = monomial.entry(variable.clone()).or_insert(0);
+                    *e = e.checked_add(*exponent)?;
+                }
+                result.checked_add_term(monomial, c.checked_mul(d)?)?;
+            }
+        }
+        Some(result)
+    }
+
+    pub fn checked_pow(&self, exponent: u32) -> Option<Polynomial> {
+        let mut result = Polynomial::constant(Rational32::one());
+        let mut square = self.clone();
+        let mut remaining = exponent;
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                square = square.checked_mul(&square)?;
+            }
+        }
+        Some(result)
+    }
+
+    pub fn evaluate(&self, values: &HashMap<String, Rational32>) -> Option<Rational32> {
+        let mut result = Rational32::zero();
+        for (monomial, c) in self.terms.iter() {
+            let mut term = *c;
+            for (variable, exponent) in monomial.iter() {
+                let value = values.get(variable)?;
+                term = term.checked_mul(&checked_pow(*value, i32::try_from(*exponent).ok()?)?)?;
+            }
+            result = result.checked_add(&term)?;
+        }
+        Some(result)
+    }
+
+    // The factor c with self == c * other, if there is one.
+    pub fn scalar_multiple_of(&self, other: &Polynomial) -> Option<Rational32> {
+        if self.terms.len() != other.terms.len() {
+            return None;
+        }
+        let (monomial, c) = other.terms.iter().next()?;
+        let factor = self.coefficient(monomial).checked_div(c)?;
+        if other.checked_scale(factor)? == *self {
+            Some(factor)
+        } else {
+            None
+        }
+    }
+
+    fn add_term(&mut self, monomial: Monomial, c: Rational32) {
+        self.checked_add_term(monomial, c).unwrap();
+    }
+
+    fn checked_add_term(&mut self, monomial: Monomial, c: Rational32) -> Option<()> {
+        if c.is_zero() {
+            return Some(());
+        }
+        let sum = self.coefficient(&monomial).checked_add(&c)?;
+        if sum.is_zero() {
+            self.terms.remove(&monomial);
+        } else {
+            self.terms.insert(monomial, sum);
+        }
+        Some(())
+    }
+}
+
+struct PolynomialBuilder;
+
+impl Fold for PolynomialBuilder {
+    type Output = Option<Polynomial>;
+
+    fn fold_number(&mut self, number: &str) -> Option<Polynomial> {
+        Some(Polynomial::constant(Rational32::from(number.parse::<i32>().ok()?)))
+    }
+
+    fn fold_variable(&mut self, name: &str) -> Option<Polynomial> {
+        Some(Polynomial::variable(name))
+    }
+
+    fn fold_negation(&mut self, inner: Option<Polynomial>) -> Option<Polynomial> {
+        inner?.checked_neg()
+    }
+
+    fn fold_composite(&mut self, operator: &Operator, left: Option<Polynomial>, right: Option<Polynomial>) -> Option<Polynomial> {
+        let left = left?;
+        let right = right?;
+        match operator {
+            Operator::PLUS => left.checked_add(&right),
+            Operator::MINUS => left.checked_sub(&right),
+            Operator::MULT => left.checked_mul(&right),
+            Operator::DIV => {
+                let divisor = right.as_constant()?;
+                if divisor.is_zero() {
+                    return None;
+                }
+                left.checked_scale(divisor.recip())
+            },
+            Operator::POW => {
+                let exponent = right.as_constant()?;
+                if !exponent.is_integer() || *exponent.numer() < 0 {
+                    return None;
+                }
+                left.checked_pow(*exponent.numer() as u32)
+            }
+        }
+    }
+
+    // sq is a polynomial operation. Other functions are only allowed
+    // on constants with a rational value.
+    fn fold_call(&mut self, name: &str, arguments: Vec<Option<Polynomial>>) -> Option<Polynomial> {
+        let arguments: Vec<Polynomial> = arguments.into_iter().collect::<Option<_>>()?;
+        if name == "sq" && arguments.len() == 1 {
+            return arguments[0].checked_pow(2);
+        }
+        let values: Vec<Surd> = arguments.iter()
+            .map(|a| a.as_constant().map(Surd::from_rational))
+            .collect::<Option<_>>()?;
+        Some(Polynomial::constant(apply_function(name, &values)?.to_rational()?))
+    }
+}
+
+impl ToString for Polynomial {
+    fn to_string(&self) -> String {
+        if self.is_zero() {
+            return String::from("0");
+        }
+        let mut result = String::new();
+        for (monomial, c) in self.terms.iter().rev() {
+            let magnitude = if *c < Rational32::zero() {
+                result.push_str(if result.is_empty() { "-" } else { " - " });
+                checked_neg(*c).unwrap()
+            } else {
+                if !result.is_empty() {
+                    result.push_str(" + ");
+                }
+                *c
+            };
+            let mut factors: Vec<String> = Vec::new();
+            if !magnitude.is_one() || monomial.is_empty() {
+                factors.push(magnitude.to_string());
+            }
+            for (variable, exponent) in monomial.iter() {
+                if *exponent == 1 {
+                    factors.push(variable.clone());
+                } else {
+                    factors.push(format!("{}^{}", variable, exponent));
+                }
+            }
+            result.push_str(&factors.join("*"));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+
+    fn polynomial(input: &str) -> Option<Polynomial> {
+        Polynomial::from_expression(&ExprParser::new().parse(input).unwrap())
+    }
+
+    #[test]
+    fn normal_form() {
+        assert_eq!(polynomial("(x + 1)^2").unwrap().to_string(), "x^2 + 2*x + 1");
+        assert_eq!(polynomial("(x - y)*(x + y) + y^2").unwrap().to_string(), "x^2");
+        assert_eq!(polynomial("x/2 - 3/4*y*x").unwrap().to_string(), "-3/4*x*y + 1/2*x");
+        assert_eq!(polynomial("x - x").unwrap().to_string(), "0");
+        assert_eq!(polynomial("sq(x - 1) + sqrt(4)*x").unwrap().to_string(), "x^2 + 1");
+    }
+
+    #[test]
+    fn equal_normal_forms() {
+        assert_eq!(polynomial("(x + y)^2"), polynomial("x^2 + 2*x*y + y^2"));
+        assert_ne!(polynomial("(x + y)^2"), polynomial("x^2 + y^2"));
+    }
+
+    #[test]
+    fn not_a_polynomial() {
+        assert_eq!(polynomial("1/x"), None);
+        assert_eq!(polynomial("x^-1"), None);
+        assert_eq!(polynomial("x^y"), None);
+        assert_eq!(polynomial("x/0"), None);
+        assert_eq!(polynomial("x/(x - 1)"), None);
+        assert_eq!(polynomial("sqrt(x)"), None);
+        assert_eq!(polynomial("sqrt(2)*x"), None);
+    }
+
+    #[test]
+    fn degrees() {
+        let p = polynomial("x^2*y + y^3 + x").unwrap();
+        assert_eq!(p.degree(), 3);
+        assert_eq!(p.degree_in("x"), 2);
+        assert_eq!(p.degree_in("z"), 0);
+    }
+
+    #[test]
+    fn scalar_multiple() {
+        let p = polynomial("x^2 + 2*y - 1").unwrap();
+        let q = polynomial("-3*x^2 - 6*y + 3").unwrap();
+        assert_eq!(q.scalar_multiple_of(&p), Some(Rational32::from(-3)));
+        assert_eq!(p.scalar_multiple_of(&polynomial("x^2 + 2*y").unwrap()), None);
+    }
+
+    #[test]
+    fn evaluate() {
+        let p = polynomial("x^2*y - 1/2").unwrap();
+        let mut values = HashMap::new();
+        values.insert(String::from("x"), Rational32::from(3));
+        values.insert(String::from("y"), Rational32::new(1, 3));
+        assert_eq!(p.evaluate(&values), Some(Rational32::new(5, 2)));
+    }
+}
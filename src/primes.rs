@@ -1,6 +1,8 @@
 include!(concat!(env!("OUT_DIR"), "/primes.rs"));
 include!("primes_include.rs");
 
+use num_traits::PrimInt;
+use num_traits::Signed;
 use crate::generation::get_primes::sqrt_floor;
 
 #[derive(Debug, PartialEq)]
@@ -34,6 +36,72 @@ pub fn factors(n: u32) -> Vec<Factor> {
     }
 }
 
+// All positive divisors of n in increasing order, built from the prime
+// factorization as the products of powers of its primes.
+pub fn divisors(n: u32) -> Vec<u32> {
+    if n == 0 {
+        panic!("Cannot calculate divisors of 0");
+    }
+    let mut result: Vec<u32> = vec![1];
+    if n == 1 {
+        return result;
+    }
+    for Factor{factor, count} in factors(n) {
+        let mut next: Vec<u32> = Vec::new();
+        for divisor in result.iter() {
+            let mut power: u32 = 1;
+            for i in 0..=count {
+                if i > 0 {
+                    power *= factor;
+                }
+                next.push(divisor * power);
+            }
+        }
+        result = next;
+    }
+    result.sort_unstable();
+    result
+}
+
+// The greatest common divisor, which is never negative, and 0 only
+// when both numbers are 0.
+pub fn gcd<T: PrimInt + Signed>(a: T, b: T) -> T {
+    if b.is_zero() {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// The least common multiple of nonzero numbers, None on overflow.
+pub fn lcm<T: PrimInt + Signed>(a: T, b: T) -> Option<T> {
+    a.checked_mul(&(b / gcd(a, b))).map(|l| l.abs())
+}
+
+// The Legendre symbol (a/p) for an odd prime p: 0 if p divides a, 1 if
+// a is a nonzero square modulo p and -1 otherwise. By Euler's criterion
+// it is a^((p-1)/2) modulo p.
+pub fn legendre_symbol(a: i64, p: u32) -> i32 {
+    if p == 2 || !is_prime(p) {
+        panic!("Cannot calculate the Legendre symbol modulo {}", p);
+    }
+    let modulus = u64::from(p);
+    let mut base = a.rem_euclid(i64::from(p)) as u64;
+    if base == 0 {
+        return 0;
+    }
+    let mut result: u64 = 1;
+    let mut exponent = (modulus - 1) / 2;
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent /= 2;
+    }
+    if result == 1 { 1 } else { -1 }
+}
+
 fn next_factor(n: u32, factor: u32) -> (u32, u32) {
     let mut remaining = n;
     let mut count: u32 = 0;
@@ -52,7 +120,11 @@ fn next_factor(n: u32, factor: u32) -> (u32, u32) {
 #[cfg(test)]
 mod test {
     use super::Factor;
+    use super::divisors;
     use super::factors;
+    use super::gcd;
+    use super::lcm;
+    use super::legendre_symbol;
 
     #[test]
     fn test_we_do_not_have_reduced_primes_array() {
@@ -91,4 +163,38 @@ mod test {
     fn factors_twelve() {
         assert_eq!(vec![Factor{factor:2, count:2}, Factor{factor:3, count:1}], factors(12));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(6, gcd(12, -18));
+        assert_eq!(5, gcd(0i64, -5));
+        assert_eq!(0, gcd(0i128, 0));
+        assert_eq!(Some(36), lcm(12, -18));
+        assert_eq!(Some(7), lcm(1i64, 7));
+        assert_eq!(None, lcm(i32::MAX, i32::MAX - 1));
+    }
+
+    #[test]
+    fn divisors_of_small_numbers() {
+        assert_eq!(vec![1], divisors(1));
+        assert_eq!(vec![1, 7], divisors(7));
+        assert_eq!(vec![1, 2, 3, 4, 6, 12], divisors(12));
+        assert_eq!(vec![1, 3, 9, 11, 33, 99], divisors(99));
+    }
+
+    #[test]
+    fn divisors_of_large_prime_power() {
+        assert_eq!(vec![1, 65521, 65521 * 65521], divisors(65521 * 65521));
+        assert_eq!(32, divisors(1 << 31).len());
+    }
+
+    #[test]
+    fn legendre_symbols() {
+        let squares: Vec<i64> = (1..11).filter(|a| legendre_symbol(*a, 11) == 1).collect();
+        assert_eq!(vec![1, 3, 4, 5, 9], squares);
+        assert_eq!(-1, legendre_symbol(-1, 7));
+        assert_eq!(1, legendre_symbol(-1, 13));
+        assert_eq!(0, legendre_symbol(-26, 13));
+        assert_eq!(1, legendre_symbol(2, 65521));
+    }
+}
@@ -0,0 +1,274 @@
+// Geometric properties of conics, computed from their standard form.
+//
+// Values are exact quadratic surds where possible. Lengths like the
+// semi-axes are square roots of surds and are only exact when the
+// square is rational; values that are not exact are Real::Approximate.
+
+use ::num_rational::Rational32;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::Zero;
+use crate::conic::Conic;
+use crate::line::Line;
+use crate::standard_form::Shape;
+use crate::standard_form::standard_form;
+use crate::surd::Real;
+use crate::surd::Surd;
+
+pub type Point = (Real, Real);
+
+// Properties of an ellipse or a hyperbola. For a hyperbola the major
+// axis is the transverse axis, the one through the vertices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CentralProperties {
+    pub center: (Surd, Surd),
+    // a and b of the standard form
+    pub semi_axes: (Real, Real),
+    // A unit vector along the major axis.
+    pub major_axis: (Real, Real),
+    pub foci: [Point; 2],
+    pub vertices: [Point; 2],
+    pub eccentricity: Real,
+    pub asymptotes: Option<[Line; 2]>
+}
+
+// Properties of a parabola y'^2 = 4 p x' in standard form. The focal
+// length p is the distance from the vertex to the focus, the focal
+// parameter 2p the distance from the focus to the directrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParabolaProperties {
+    pub vertex: (Surd, Surd),
+    pub focus: (Surd, Surd),
+    pub directrix: Line,
+    pub axis: Line,
+    pub focal_length: Surd,
+    pub focal_parameter: Surd
+}
+
+// None unless the conic is an ellipse or a hyperbola.
+pub fn central_properties(conic: &Conic) -> Option<CentralProperties> {
+    let form = standard_form(conic)?;
+    let (a_squared, b_squared, hyperbola) = match form.shape {
+        Shape::Ellipse{a_squared, b_squared} => (a_squared, b_squared, false),
+        Shape::Hyperbola{a_squared, b_squared} => (a_squared, b_squared, true),
+        Shape::Parabola{..} => return None
+    };
+    // c^2 = a^2 - b^2 for an ellipse and a^2 + b^2 for a hyperbola
+    let c_squared = if hyperbola {
+        a_squared.checked_add(&b_squared)?
+    } else {
+        a_squared.checked_sub(&b_squared)?
+    };
+    let a = Real::from(a_squared).sqrt();
+    let c = Real::from(c_squared).sqrt();
+    let direction = (form.rotation.cos, form.rotation.sin);
+    let center = (Real::from(form.origin.0), Real::from(form.origin.1));
+    let along = |distance: Real| (center.0 + distance * direction.0, center.1 + distance * direction.1);
+    let asymptotes = if hyperbola {
+        Some(asymptotes(conic, form.origin)?)
+    } else {
+        None
+    };
+    Some(CentralProperties {
+        center: form.origin,
+        semi_axes: (a, Real::from(b_squared).sqrt()),
+        major_axis: direction,
+        foci: [along(-c), along(c)],
+        vertices: [along(-a), along(a)],
+        eccentricity: (Real::from(c_squared) / Real::from(a_squared)).sqrt(),
+        asymptotes
+    })
+}
+
+// None unless the conic is a parabola. All values are exact, because
+// the rotation of a parabola is.
+pub fn parabola_properties(conic: &Conic) -> Option<ParabolaProperties> {
+    let form = standard_form(conic)?;
+    let p = match form.shape {
+        Shape::Parabola{p} => p,
+        _ => return None
+    };
+    let (cos, sin) = (form.rotation.cos.exact()?, form.rotation.sin.exact()?);
+    let (h, k) = form.origin;
+    let focus = (h.checked_add(&p.checked_mul(&cos)?)?, k.checked_add(&p.checked_mul(&sin)?)?);
+    // cos x + sin y = cos h + sin k - p, perpendicular to the axis
+    let distance = cos.checked_mul(&h)?.checked_add(&sin.checked_mul(&k)?)?.checked_sub(&p)?;
+    let directrix = Line::new(cos, sin, distance.checked_neg()?).normalized()?;
+    Some(ParabolaProperties {
+        vertex: form.origin,
+        focus,
+        directrix,
+        axis: Line::through(form.origin, (cos, sin))?.normalized()?,
+        focal_length: p,
+        focal_parameter: p.checked_add(&p)?
+    })
+}
+
+// The quadratic part A X^2 + B XY + C Y^2 in X = x - h, Y = y - k
+// splits into two real lines for a hyperbola. With A = 0 they are
+// Y = 0 and B X + C Y = 0. Otherwise they are X - t Y = 0 for the
+// roots t = (-B +- sqrt(B^2 - 4AC)) / 2A of A t^2 + B t + C.
+fn asymptotes(conic: &Conic, center: (Surd, Surd)) -> Option<[Line; 2]> {
+    let Conic{a, b, c, ..} = *conic;
+    let directions = if a.is_zero() {
+        [(Surd::zero(), Surd::one()), (Surd::from_rational(b), Surd::from_rational(c))]
+    } else {
+        let four_ac = Rational32::from(4).checked_mul(&a)?.checked_mul(&c)?;
+        let root = Surd::sqrt(b.checked_mul(&b)?.checked_sub(&four_ac)?)?;
+        let minus_b = Surd::from_rational(-b);
+        let two_a = Surd::from_rational(a.checked_mul(&Rational32::from(2))?);
+        let first = minus_b.checked_sub(&root)?.checked_div(&two_a)?;
+        let second = minus_b.checked_add(&root)?.checked_div(&two_a)?;
+        [(Surd::one(), first.checked_neg()?), (Surd::one(), second.checked_neg()?)]
+    };
+    let line = |(x, y): (Surd, Surd)| -> Option<Line> {
+        let c = x.checked_mul(&center.0)?.checked_add(&y.checked_mul(&center.1)?)?.checked_neg()?;
+        Some(Line::new(x, y, c))
+    };
+    Some([line(directions[0])?, line(directions[1])?])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::EquationParser;
+
+    fn properties(input: &str) -> Option<CentralProperties> {
+        central_properties(&Conic::from_equation(&EquationParser::new().parse(input).unwrap()).unwrap())
+    }
+
+    fn point(p: &Point) -> String {
+        format!("({}, {})", p.0.to_string(), p.1.to_string())
+    }
+
+    #[test]
+    fn ellipse() {
+        let p = properties("x^2/25 + y^2/9 = 1").unwrap();
+        assert_eq!((p.semi_axes.0.to_string(), p.semi_axes.1.to_string()), (String::from("5"), String::from("3")));
+        assert_eq!(point(&p.foci[0]), "(-4, 0)");
+        assert_eq!(point(&p.foci[1]), "(4, 0)");
+        assert_eq!(point(&p.vertices[1]), "(5, 0)");
+        assert_eq!(p.eccentricity.to_string(), "4/5");
+        assert_eq!(p.asymptotes, None);
+    }
+
+    #[test]
+    fn rotated_and_translated_ellipse() {
+        let p = properties("5*(x - 1)^2 + 4*(x - 1)*(y - 2) + 8*(y - 2)^2 = 36").unwrap();
+        assert_eq!((p.center.0.to_string(), p.center.1.to_string()), (String::from("1"), String::from("2")));
+        assert_eq!(point(&p.major_axis), "(2/5*sqrt(5), -1/5*sqrt(5))");
+        assert_eq!(point(&p.foci[0]), "(-1, 3)");
+        assert_eq!(point(&p.foci[1]), "(3, 1)");
+        assert_eq!(point(&p.vertices[1]), "(1 + 6/5*sqrt(5), 2 - 3/5*sqrt(5))");
+        assert_eq!(p.eccentricity.to_string(), "1/3*sqrt(5)");
+    }
+
+    #[test]
+    fn circle() {
+        let p = properties("x^2 + y^2 = 2").unwrap();
+        assert_eq!(p.semi_axes.0.to_string(), "sqrt(2)");
+        assert_eq!(point(&p.foci[0]), "(0, 0)");
+        assert_eq!(p.eccentricity.to_string(), "0");
+    }
+
+    #[test]
+    fn hyperbola() {
+        let p = properties("x^2/9 - y^2/16 = 1").unwrap();
+        assert_eq!(point(&p.foci[1]), "(5, 0)");
+        assert_eq!(p.eccentricity.to_string(), "5/3");
+        let asymptotes = p.asymptotes.unwrap();
+        assert_eq!(asymptotes[0].to_string(), "x + 3/4*y = 0");
+        assert_eq!(asymptotes[1].to_string(), "x - 3/4*y = 0");
+    }
+
+    #[test]
+    fn rectangular_hyperbola() {
+        let p = properties("x*y = 1").unwrap();
+        assert_eq!(point(&p.foci[1]), "(sqrt(2), sqrt(2))");
+        assert_eq!(point(&p.vertices[0]), "(-1, -1)");
+        assert_eq!(p.eccentricity.to_string(), "sqrt(2)");
+        let asymptotes = p.asymptotes.unwrap();
+        assert_eq!(asymptotes[0].to_string(), "y = 0");
+        assert_eq!(asymptotes[1].to_string(), "x = 0");
+    }
+
+    #[test]
+    fn translated_hyperbola_with_surd_asymptotes() {
+        let p = properties("x^2 - 2*y^2 - 2*x = 1").unwrap();
+        let asymptotes = p.asymptotes.unwrap();
+        assert_eq!(asymptotes[0].to_string(), "x + sqrt(2)*y - 1 = 0");
+        assert_eq!(asymptotes[1].to_string(), "x - sqrt(2)*y - 1 = 0");
+    }
+
+    #[test]
+    fn approximate_values() {
+        let p = properties("x^2 + x*y + 2*y^2 = 1").unwrap();
+        assert!(!p.semi_axes.0.is_exact());
+        assert!(!p.foci[0].0.is_exact());
+        // a^2 = (6 + 2 sqrt(2)) / 7
+        let a = ((6.0 + 2.0 * 2f64.sqrt()) / 7.0).sqrt();
+        assert!((p.semi_axes.0.to_f64() - a).abs() < 1e-12);
+        assert_eq!(p.center.0.to_string(), "0");
+    }
+
+    fn parabola(input: &str) -> Option<ParabolaProperties> {
+        parabola_properties(&Conic::from_equation(&EquationParser::new().parse(input).unwrap()).unwrap())
+    }
+
+    fn surd_point(p: &(Surd, Surd)) -> String {
+        format!("({}, {})", p.0.to_string(), p.1.to_string())
+    }
+
+    #[test]
+    fn axis_parallel_parabola() {
+        let p = parabola("(y - 1)^2 = -8*(x - 3)").unwrap();
+        assert_eq!(surd_point(&p.vertex), "(3, 1)");
+        assert_eq!(surd_point(&p.focus), "(1, 1)");
+        assert_eq!(p.directrix.to_string(), "x - 5 = 0");
+        assert_eq!(p.axis.to_string(), "y - 1 = 0");
+        assert_eq!(p.focal_length.to_string(), "2");
+        assert_eq!(p.focal_parameter.to_string(), "4");
+    }
+
+    #[test]
+    fn graph_of_quadratic() {
+        let p = parabola("y = x^2 - 2*x").unwrap();
+        assert_eq!(surd_point(&p.vertex), "(1, -1)");
+        assert_eq!(surd_point(&p.focus), "(1, -3/4)");
+        assert_eq!(p.directrix.to_string(), "y + 5/4 = 0");
+        assert_eq!(p.axis.to_string(), "x - 1 = 0");
+    }
+
+    #[test]
+    fn rotated_parabola() {
+        let p = parabola("x^2 - 2*x*y + y^2 = x + y").unwrap();
+        assert_eq!(surd_point(&p.vertex), "(0, 0)");
+        assert_eq!(surd_point(&p.focus), "(1/8, 1/8)");
+        assert_eq!(p.directrix.to_string(), "x + y + 1/4 = 0");
+        assert_eq!(p.axis.to_string(), "x - y = 0");
+        assert_eq!(p.focal_parameter.to_string(), "1/4*sqrt(2)");
+    }
+
+    #[test]
+    fn parabola_from_focus_and_directrix() {
+        // The points at equal distance from (1, 0) and the line x + 2y = 0
+        let p = parabola("5*((x - 1)^2 + y^2) = (x + 2*y)^2").unwrap();
+        assert_eq!(surd_point(&p.focus), "(1, 0)");
+        assert_eq!(p.directrix.to_string(), "x + 2*y = 0");
+        assert_eq!(p.axis.to_string(), "x - 1/2*y - 1 = 0");
+        assert_eq!(surd_point(&p.vertex), "(9/10, -1/5)");
+        assert_eq!(p.focal_parameter.to_string(), "1/5*sqrt(5)");
+    }
+
+    #[test]
+    fn not_a_parabola() {
+        assert_eq!(parabola("x^2 + y^2 = 1"), None);
+        assert_eq!(parabola("(x + y)^2 = 1"), None);
+    }
+
+    #[test]
+    fn not_central() {
+        assert_eq!(properties("y = x^2"), None);
+        assert_eq!(properties("x^2 - y^2 = 0"), None);
+    }
+}
@@ -0,0 +1,84 @@
+// Real roots of a t^2 + b t + c = 0 for surd coefficients.
+//
+// The roots are (-b +- sqrt(b^2 - 4ac)) / 2a. They are quadratic
+// surds when the discriminant is rational, which is always the case
+// for rational coefficients.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use crate::surd::Surd;
+
+// The distinct real roots in increasing order. A linear equation
+// (a = 0) has its single root, and 0 = 0 and c = 0 with c nonzero have
+// none. None on overflow or when the discriminant is irrational.
+pub fn solve_quadratic(a: &Surd, b: &Surd, c: &Surd) -> Option<Vec<Surd>> {
+    if a.is_zero() {
+        if b.is_zero() {
+            return Some(Vec::new());
+        }
+        return Some(vec![c.checked_neg()?.checked_div(b)?]);
+    }
+    let four_ac = Surd::from_rational(Rational32::from(4)).checked_mul(a)?.checked_mul(c)?;
+    let discriminant = b.checked_mul(b)?.checked_sub(&four_ac)?;
+    let two_a = a.checked_add(a)?;
+    let minus_b = b.checked_neg()?;
+    match discriminant.signum() {
+        Ordering::Less => Some(Vec::new()),
+        Ordering::Equal => Some(vec![minus_b.checked_div(&two_a)?]),
+        Ordering::Greater => {
+            let root = discriminant.checked_sqrt()?;
+            let mut roots = vec![
+                minus_b.checked_sub(&root)?.checked_div(&two_a)?,
+                minus_b.checked_add(&root)?.checked_div(&two_a)?
+            ];
+            // The square root is positive, so the roots are in
+            // increasing order unless 2a is negative.
+            if two_a.signum() == Ordering::Less {
+                roots.reverse();
+            }
+            Some(roots)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn r(n: i32) -> Surd {
+        Surd::from_rational(Rational32::from(n))
+    }
+
+    fn roots(a: i32, b: i32, c: i32) -> Vec<String> {
+        solve_quadratic(&r(a), &r(b), &r(c)).unwrap().iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn rational_roots() {
+        assert_eq!(roots(1, 1, -12), vec!["-4", "3"]);
+        assert_eq!(roots(-2, 0, 2), vec!["-1", "1"]);
+        assert_eq!(roots(4, -4, 1), vec!["1/2"]);
+    }
+
+    #[test]
+    fn surd_roots() {
+        assert_eq!(roots(1, -2, -1), vec!["1 - sqrt(2)", "1 + sqrt(2)"]);
+        assert_eq!(roots(2, 0, -3), vec!["-1/2*sqrt(6)", "1/2*sqrt(6)"]);
+    }
+
+    #[test]
+    fn degenerate() {
+        assert_eq!(roots(1, 0, 1), Vec::<String>::new());
+        assert_eq!(roots(0, 2, -3), vec!["3/2"]);
+        assert_eq!(roots(0, 0, 1), Vec::<String>::new());
+    }
+
+    #[test]
+    fn irrational_discriminant() {
+        let sqrt2 = Surd::sqrt(Rational32::from(2)).unwrap();
+        assert_eq!(solve_quadratic(&r(1), &sqrt2, &r(-1)), None);
+        // t^2 - 2 sqrt(2) t + 1 has discriminant 4
+        let roots = solve_quadratic(&r(1), &sqrt2.checked_mul(&r(-2)).unwrap(), &r(1)).unwrap();
+        assert_eq!(roots.iter().map(|s| s.to_string()).collect::<Vec<_>>(), vec!["-1 + sqrt(2)", "1 + sqrt(2)"]);
+    }
+}
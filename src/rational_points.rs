@@ -0,0 +1,390 @@
+// Rational points on conics.
+//
+// A change of coordinates brings the quadratic form of the conic to a
+// u^2 + b v^2 + c w^2 with integers a, b, c, which can be taken square
+// free and pairwise coprime. By Legendre's theorem it has a nontrivial
+// rational zero exactly when a, b, c do not all have the same sign and
+// -bc, -ca, -ab are squares modulo |a|, |b|, |c|, and then there is a
+// zero with |u| <= sqrt|bc|, |v| <= sqrt|ca| and |w| <= sqrt|ab|,
+// which is found by a search. The lines through one rational point meet
+// the conic again in all the others, which gives a parametrization by
+// their slopes.
+
+use ::num_rational::Rational32;
+use ::num_rational::Rational64;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::One;
+use num_traits::Signed;
+use num_traits::Zero;
+use crate::conic::Conic;
+use crate::conic::Matrix3;
+use crate::poly::Poly;
+use crate::primes::Factor;
+use crate::primes::factors;
+use crate::primes::gcd;
+use crate::primes::lcm;
+use crate::primes::legendre_symbol;
+
+// The largest number of candidates the search for a point tries.
+const SEARCH_LIMIT: i64 = 1 << 22;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RationalPoints {
+    // The conic has no real points.
+    NotReal,
+    // The conic has real points but no rational ones, since its
+    // equation has no nontrivial solution modulo this prime.
+    Obstructed(u32),
+    Parametrized(Parametrization)
+}
+
+// The line through the point with slope t meets the conic again in
+// (x(t) / w(t), y(t) / w(t)). The vertical line through the point gives
+// the remaining one in the limit of large t.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Parametrization {
+    pub point: (Rational32, Rational32),
+    pub x: Poly,
+    pub y: Poly,
+    pub w: Poly
+}
+
+impl Parametrization {
+    // None on overflow and when w(t) = 0, where the line meets the conic
+    // only at the point and at infinity.
+    pub fn at(&self, t: Rational32) -> Option<(Rational32, Rational32)> {
+        let w = self.w.evaluate(t)?;
+        if w.is_zero() {
+            return None;
+        }
+        Some((self.x.evaluate(t)?.checked_div(&w)?, self.y.evaluate(t)?.checked_div(&w)?))
+    }
+}
+
+// None on overflow, for degenerate conics and when the coefficients are
+// too large to search for a point.
+pub fn rational_points(conic: &Conic) -> Option<RationalPoints> {
+    if conic.determinant()?.is_zero() {
+        return None;
+    }
+    let (basis, diagonal) = diagonalize(conic.matrix()?)?;
+    if diagonal.iter().all(Rational32::is_positive) || diagonal.iter().all(Rational32::is_negative) {
+        return Some(RationalPoints::NotReal);
+    }
+    let form = Reduced::new(diagonal)?;
+    if let Some(p) = form.obstruction()? {
+        return Some(RationalPoints::Obstructed(p));
+    }
+    let zero = form.zero()?;
+    let mut projective = [Rational32::zero(); 3];
+    for (u, vector) in zero.iter().zip(basis.iter()) {
+        for (x, component) in projective.iter_mut().zip(vector.iter()) {
+            *x = x.checked_add(&u.checked_mul(component)?)?;
+        }
+    }
+    let point = affine_point(conic, projective)?;
+    Some(RationalPoints::Parametrized(parametrize(conic, point)?))
+}
+
+// With the slope t, the line through (x0, y0) meets the conic where
+// w(t) s^2 + beta(t) s = 0 for w(t) = A + B t + C t^2 and the gradient
+// beta(t) of the equation at the point in the direction (1, t).
+fn parametrize(conic: &Conic, point: (Rational32, Rational32)) -> Option<Parametrization> {
+    let (x0, y0) = point;
+    let (gx, gy) = gradient(conic, point)?;
+    let w = Poly::new(vec![conic.a, conic.b, conic.c]);
+    let beta = Poly::new(vec![gx, gy]);
+    Some(Parametrization {
+        point,
+        x: w.checked_scale(x0)?.checked_sub(&beta)?,
+        y: w.checked_scale(y0)?.checked_sub(&beta.checked_mul(&Poly::x())?)?,
+        w
+    })
+}
+
+// The affine point of a projective point (x, y, w). A point (p, q, 0)
+// at infinity is the direction of an asymptote or the axis, and the
+// lines in that direction meet the conic in one more point, except for
+// an asymptote itself.
+fn affine_point(conic: &Conic, projective: [Rational32; 3]) -> Option<(Rational32, Rational32)> {
+    let [x, y, w] = projective;
+    if !w.is_zero() {
+        return Some((x.checked_div(&w)?, y.checked_div(&w)?));
+    }
+    for k in [0, 1] {
+        let k = Rational32::from(k);
+        let base = (k.checked_mul(&y)?.checked_mul(&Rational32::from(-1))?, k.checked_mul(&x)?);
+        let (gx, gy) = gradient(conic, base)?;
+        let beta = gx.checked_mul(&x)?.checked_add(&gy.checked_mul(&y)?)?;
+        if !beta.is_zero() {
+            let s = value(conic, base)?.checked_div(&beta)?.checked_mul(&Rational32::from(-1))?;
+            return Some((base.0.checked_add(&s.checked_mul(&x)?)?, base.1.checked_add(&s.checked_mul(&y)?)?));
+        }
+    }
+    None
+}
+
+fn value(conic: &Conic, (x, y): (Rational32, Rational32)) -> Option<Rational32> {
+    let terms = [
+        conic.a.checked_mul(&x)?.checked_mul(&x)?,
+        conic.b.checked_mul(&x)?.checked_mul(&y)?,
+        conic.c.checked_mul(&y)?.checked_mul(&y)?,
+        conic.d.checked_mul(&x)?,
+        conic.e.checked_mul(&y)?,
+        conic.f
+    ];
+    terms.iter().try_fold(Rational32::zero(), |sum, term| sum.checked_add(term))
+}
+
+// (2A x + B y + D, B x + 2C y + E)
+fn gradient(conic: &Conic, (x, y): (Rational32, Rational32)) -> Option<(Rational32, Rational32)> {
+    let two = Rational32::from(2);
+    Some((
+        two.checked_mul(&conic.a)?.checked_mul(&x)?.checked_add(&conic.b.checked_mul(&y)?)?.checked_add(&conic.d)?,
+        conic.b.checked_mul(&x)?.checked_add(&two.checked_mul(&conic.c)?.checked_mul(&y)?)?.checked_add(&conic.e)?))
+}
+
+// Basis vectors e1, e2, e3 with e_i^T M e_j = 0 for i != j, and the
+// diagonal entries e_i^T M e_i, by symmetric elimination. A zero pivot
+// is replaced by a later nonzero diagonal entry or, if there is none,
+// by adding a later basis vector.
+fn diagonalize(mut m: Matrix3) -> Option<(Matrix3, [Rational32; 3])> {
+    let (zero, one) = (Rational32::zero(), Rational32::one());
+    let mut basis = [[one, zero, zero], [zero, one, zero], [zero, zero, one]];
+    for k in 0..3 {
+        if m[k][k].is_zero() {
+            if let Some(j) = (k + 1..3).find(|&j| !m[j][j].is_zero()) {
+                m.swap(k, j);
+                for row in m.iter_mut() {
+                    row.swap(k, j);
+                }
+                basis.swap(k, j);
+            } else if let Some(j) = (k + 1..3).find(|&j| !m[k][j].is_zero()) {
+                add(&mut m, &mut basis, k, j, one)?;
+            } else {
+                continue;
+            }
+        }
+        for j in k + 1..3 {
+            let factor = m[k][j].checked_div(&m[k][k])?.checked_mul(&Rational32::from(-1))?;
+            add(&mut m, &mut basis, j, k, factor)?;
+        }
+    }
+    Some((basis, [m[0][0], m[1][1], m[2][2]]))
+}
+
+// Replaces the basis vector e_target by e_target + factor e_source.
+fn add(m: &mut Matrix3, basis: &mut Matrix3, target: usize, source: usize, factor: Rational32) -> Option<()> {
+    let combine = |target: [Rational32; 3], source: [Rational32; 3]| -> Option<[Rational32; 3]> {
+        Some([
+            target[0].checked_add(&factor.checked_mul(&source[0])?)?,
+            target[1].checked_add(&factor.checked_mul(&source[1])?)?,
+            target[2].checked_add(&factor.checked_mul(&source[2])?)?])
+    };
+    m[target] = combine(m[target], m[source])?;
+    for row in m.iter_mut() {
+        row[target] = row[target].checked_add(&factor.checked_mul(&row[source])?)?;
+    }
+    basis[target] = combine(basis[target], basis[source])?;
+    Some(())
+}
+
+// The form a u^2 + b v^2 + c w^2 with square free, pairwise coprime
+// integer coefficients, in the variables U = u / scale of the original
+// diagonal form.
+struct Reduced {
+    coefficients: [i64; 3],
+    scales: [Rational64; 3]
+}
+
+impl Reduced {
+    // Multiplying by the common denominator gives integers. A square
+    // factor s^2 of a moves into the variable, U = s u, and a common
+    // factor g of a and b moves into c after multiplying by g, as
+    // g a u^2 = (a/g) (g u)^2.
+    fn new(diagonal: [Rational32; 3]) -> Option<Reduced> {
+        let denominator = diagonal.iter().try_fold(1i64, |l, d| lcm(l, i64::from(*d.denom())))?;
+        let mut coefficients = [0i64; 3];
+        for (coefficient, d) in coefficients.iter_mut().zip(diagonal.iter()) {
+            *coefficient = i64::from(*d.numer()).checked_mul(denominator / i64::from(*d.denom()))?;
+        }
+        let mut form = Reduced{coefficients, scales: [Rational64::one(); 3]};
+        for i in 0..3 {
+            form.remove_square(i)?;
+        }
+        loop {
+            let Some((i, j, g)) = [(0, 1), (1, 2), (0, 2)].into_iter()
+                .map(|(i, j)| (i, j, gcd(form.coefficients[i], form.coefficients[j])))
+                .find(|(_, _, g)| *g > 1) else {
+                return Some(form);
+            };
+            let k = 3 - i - j;
+            form.coefficients[i] /= g;
+            form.coefficients[j] /= g;
+            form.coefficients[k] = form.coefficients[k].checked_mul(g)?;
+            form.scales[i] = form.scales[i].checked_div(&Rational64::from(g))?;
+            form.scales[j] = form.scales[j].checked_div(&Rational64::from(g))?;
+            form.remove_square(k)?;
+        }
+    }
+
+    fn remove_square(&mut self, i: usize) -> Option<()> {
+        let n = u32::try_from(self.coefficients[i].unsigned_abs()).ok()?;
+        if n == 1 {
+            return Some(());
+        }
+        for Factor{factor, count} in factors(n) {
+            let root = i64::from(factor).checked_pow(count / 2)?;
+            self.coefficients[i] /= root.checked_mul(root)?;
+            self.scales[i] = self.scales[i].checked_div(&Rational64::from(root))?;
+        }
+        Some(())
+    }
+
+    // An odd prime p dividing a where -bc is not a square modulo p, or
+    // the same for b and c. Modulo 2 every number is a square.
+    fn obstruction(&self) -> Option<Option<u32>> {
+        for i in 0..3 {
+            let n = u32::try_from(self.coefficients[i].unsigned_abs()).ok()?;
+            if n == 1 {
+                continue;
+            }
+            let product = self.coefficients[(i + 1) % 3].checked_mul(self.coefficients[(i + 2) % 3])?.checked_neg()?;
+            for Factor{factor, ..} in factors(n) {
+                if factor != 2 && legendre_symbol(product, factor) != 1 {
+                    return Some(Some(factor));
+                }
+            }
+        }
+        Some(None)
+    }
+
+    // A nontrivial zero within Holzer's bounds, in the original
+    // variables. The search runs over two of the variables and solves
+    // for the one with the smallest coefficient, which makes it shortest.
+    fn zero(&self) -> Option<[Rational32; 3]> {
+        let c = &self.coefficients;
+        let k = (0..3).min_by_key(|&i| c[i].unsigned_abs())?;
+        let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+        let bound = |p: i64, q: i64| -> Option<i64> { Some(isqrt(i128::from(p.checked_mul(q)?.unsigned_abs()))? as i64) };
+        let (bi, bj) = (bound(c[j], c[k])?, bound(c[i], c[k])?);
+        if (bi + 1).checked_mul(bj + 1)? > SEARCH_LIMIT {
+            return None;
+        }
+        for x in 0..=bi {
+            for y in 0..=bj {
+                if x == 0 && y == 0 {
+                    continue;
+                }
+                let rest = -(i128::from(c[i]) * i128::from(x) * i128::from(x) + i128::from(c[j]) * i128::from(y) * i128::from(y));
+                let ck = i128::from(c[k]);
+                if rest % ck != 0 || rest / ck < 0 {
+                    continue;
+                }
+                let Some(z) = isqrt(rest / ck).filter(|z| z * z == rest / ck) else {
+                    continue;
+                };
+                let mut zero = [0i64; 3];
+                (zero[i], zero[j], zero[k]) = (x, y, i64::try_from(z).ok()?);
+                return self.unscale(zero);
+            }
+        }
+        None
+    }
+
+    // The original variables u = scale U, cleared of denominators.
+    fn unscale(&self, zero: [i64; 3]) -> Option<[Rational32; 3]> {
+        let mut result = [Rational64::zero(); 3];
+        for ((r, z), scale) in result.iter_mut().zip(zero.iter()).zip(self.scales.iter()) {
+            *r = scale.checked_mul(&Rational64::from(*z))?;
+        }
+        let denominator = result.iter().try_fold(1i64, |l, r| lcm(l, *r.denom()))?;
+        let mut narrowed = [Rational32::zero(); 3];
+        for (n, r) in narrowed.iter_mut().zip(result.iter()) {
+            let integer = r.checked_mul(&Rational64::from(denominator))?.to_integer();
+            *n = Rational32::from(i32::try_from(integer).ok()?);
+        }
+        Some(narrowed)
+    }
+}
+
+// The integer square root, None for negative numbers.
+fn isqrt(n: i128) -> Option<i128> {
+    if n < 0 {
+        return None;
+    }
+    let mut root = (n as f64).sqrt() as i128;
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    Some(root)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conic::parse_conic;
+
+    fn parametrization(input: &str) -> Parametrization {
+        match rational_points(&parse_conic(input)).unwrap() {
+            RationalPoints::Parametrized(p) => p,
+            other => panic!("Expected rational points, got {:?}", other)
+        }
+    }
+
+    // The point and some of the parametrized points are on the conic.
+    fn check(input: &str) -> Parametrization {
+        let c = parse_conic(input);
+        let p = parametrization(input);
+        assert_eq!(value(&c, p.point), Some(Rational32::zero()));
+        for t in [-3, -1, 0, 2, 5] {
+            if let Some(point) = p.at(Rational32::new(t, 2)) {
+                assert_eq!(value(&c, point), Some(Rational32::zero()), "{} at {}", input, t);
+            }
+        }
+        p
+    }
+
+    #[test]
+    fn unit_circle() {
+        let p = check("x^2 + y^2 = 1");
+        assert_eq!(p.point, (Rational32::from(1), Rational32::from(0)));
+        assert_eq!(p.w.to_string(), "x^2 + 1");
+        assert_eq!(p.x.to_string(), "x^2 - 1");
+        assert_eq!(p.y.to_string(), "-2*x");
+        assert_eq!(p.at(Rational32::from(2)), Some((Rational32::new(3, 5), Rational32::new(-4, 5))));
+    }
+
+    #[test]
+    fn points_exist() {
+        for input in ["x^2 + y^2 = 2", "x^2 + y^2 = 18", "x^2 + y^2 = 13", "x^2 + x*y + y^2 = 7", "3*x^2 - 5*y^2 = 7",
+            "x*y = 1", "y = x^2", "x = y^2 + 3*y - 1", "x^2/4 - y^2/9 = 1/36", "(x - 1/2)^2 + (y + 1/3)^2 = 25"] {
+            check(input);
+        }
+    }
+
+    #[test]
+    fn point_at_infinity() {
+        let (zero, one) = (Rational32::zero(), Rational32::one());
+        // The asymptote y = 0 itself only meets x*y = 1 at infinity.
+        assert_eq!(affine_point(&parse_conic("x*y = 1"), [one, zero, zero]), Some((one, one)));
+        assert_eq!(affine_point(&parse_conic("y = x^2 + 1"), [zero, one, zero]), Some((zero, one)));
+    }
+
+    #[test]
+    fn no_rational_points() {
+        let points = |input: &str| rational_points(&parse_conic(input)).unwrap();
+        assert_eq!(points("x^2 + y^2 = 3"), RationalPoints::Obstructed(3));
+        assert_eq!(points("3*x^2 + 5*y^2 = 7"), RationalPoints::Obstructed(3));
+        assert_eq!(points("x^2 + y^2 = 21"), RationalPoints::Obstructed(3));
+        assert_eq!(points("2*x^2 + 3*y^2 = 1"), RationalPoints::Obstructed(3));
+        assert_eq!(points("x^2 + y^2 = -1"), RationalPoints::NotReal);
+        assert_eq!(points("x^2 + x*y + y^2 + 1 = 0"), RationalPoints::NotReal);
+        assert_eq!(rational_points(&parse_conic("x^2 = y^2")), None);
+    }
+}
@@ -0,0 +1,201 @@
+// Scripts are sequences of statements separated by ";", like
+//
+//     let a = 3/4; let c = a^2 - 1; x^2/a + y^2 = c
+//
+// A let statement binds a name to an expression. Names are resolved in
+// order: every later statement sees the binding with the names it uses
+// already replaced by their values, so c above becomes (3/4)^2 - 1.
+// Variables that are never bound, like x and y, stay free.
+//
+// A bound name must not be used before its let statement and must not
+// be bound twice. Both are reported with the spans of the statements
+// involved, as byte offsets into the parsed input.
+
+use std::collections::HashMap;
+use crate::expression::Expression;
+use crate::expression::Equation;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span{start, end}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementKind {
+    Let(String, Expression),
+    Expression(Expression),
+    Equation(Equation)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Statement {
+        Statement{kind, span}
+    }
+}
+
+// The result of a statement that is not a let, with all bound names
+// replaced by their values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Expression(Expression),
+    Equation(Equation)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Output {
+    pub bindings: HashMap<String, Expression>,
+    pub values: Vec<Value>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptError {
+    // The statement at span uses a name that is bound later, at
+    // definition. A let statement that uses its own name reports
+    // itself as the definition.
+    UseBeforeDefinition{name: String, span: Span, definition: Span},
+    Redefinition{name: String, span: Span, previous: Span}
+}
+
+pub fn run(statements: &[Statement]) -> Result<Output, ScriptError> {
+    let mut definitions: HashMap<&str, Span> = HashMap::new();
+    for statement in statements.iter() {
+        if let StatementKind::Let(name, _) = &statement.kind {
+            definitions.entry(name.as_str()).or_insert(statement.span);
+        }
+    }
+    let mut bindings: HashMap<String, Expression> = HashMap::new();
+    let mut defined_at: HashMap<String, Span> = HashMap::new();
+    let mut values: Vec<Value> = Vec::new();
+    for statement in statements.iter() {
+        let check = |e: &Expression| check_uses(e, statement.span, &bindings, &definitions);
+        match &statement.kind {
+            StatementKind::Let(name, e) => {
+                check(e)?;
+                if let Some(previous) = defined_at.get(name) {
+                    return Err(ScriptError::Redefinition {
+                        name: name.clone(),
+                        span: statement.span,
+                        previous: *previous
+                    });
+                }
+                let value = e.substitute(&bindings);
+                bindings.insert(name.clone(), value);
+                defined_at.insert(name.clone(), statement.span);
+            },
+            StatementKind::Expression(e) => {
+                check(e)?;
+                values.push(Value::Expression(e.substitute(&bindings)));
+            },
+            StatementKind::Equation(e) => {
+                check(&e.left)?;
+                check(&e.right)?;
+                values.push(Value::Equation(Equation::new(
+                    Box::new(e.left.substitute(&bindings)),
+                    Box::new(e.right.substitute(&bindings)))));
+            }
+        }
+    }
+    Ok(Output{bindings, values})
+}
+
+// A name that is bound somewhere in the script has to be bound before
+// the statement at span.
+fn check_uses(e: &Expression, span: Span, bindings: &HashMap<String, Expression>, definitions: &HashMap<&str, Span>) -> Result<(), ScriptError> {
+    for name in e.variables() {
+        if bindings.contains_key(&name) {
+            continue;
+        }
+        if let Some(definition) = definitions.get(name.as_str()) {
+            return Err(ScriptError::UseBeforeDefinition{name, span, definition: *definition});
+        }
+    }
+    Ok(())
+}
+
+impl ToString for ScriptError {
+    fn to_string(&self) -> String {
+        match self {
+            ScriptError::UseBeforeDefinition{name, span, definition} =>
+                format!("{} is used at {}..{} before it is defined at {}..{}",
+                    name, span.start, span.end, definition.start, definition.end),
+            ScriptError::Redefinition{name, span, previous} =>
+                format!("{} is defined again at {}..{}, it was defined at {}..{}",
+                    name, span.start, span.end, previous.start, previous.end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ScriptParser;
+
+    fn run_script(input: &str) -> Result<Output, ScriptError> {
+        run(&ScriptParser::new().parse(input).unwrap())
+    }
+
+    fn values(input: &str) -> Vec<String> {
+        run_script(input).unwrap().values.iter().map(|v| match v {
+            Value::Expression(e) => e.to_string(),
+            Value::Equation(e) => e.to_string()
+        }).collect()
+    }
+
+    #[test]
+    fn parse_statements() {
+        let statements = ScriptParser::new().parse("let a = 3/4; x + a; x = a;").unwrap();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0].span, Span::new(0, 11));
+        assert!(matches!(statements[1].kind, StatementKind::Expression(_)));
+        assert!(matches!(statements[2].kind, StatementKind::Equation(_)));
+        assert_eq!(ScriptParser::new().parse("").unwrap(), vec![]);
+        assert!(ScriptParser::new().parse("let a = 1 = 2").is_err());
+        assert!(ScriptParser::new().parse("x;; y").is_err());
+    }
+
+    #[test]
+    fn names_are_resolved_in_order() {
+        let output = run_script("let a = 3/4; let c = a^2 - 1; x^2/a + y^2 = c").unwrap();
+        assert_eq!(output.bindings["c"].to_string(), "(((3 / 4) ^ 2) - 1)");
+        assert_eq!(values("let a = 3/4; let c = a^2 - 1; x^2/a + y^2 = c"),
+            vec!["(((x ^ 2) / (3 / 4)) + (y ^ 2)) = (((3 / 4) ^ 2) - 1)"]);
+        assert_eq!(values("let r = 5; x^2 + y^2 - r^2; letter + 1"),
+            vec!["(((x ^ 2) + (y ^ 2)) - (5 ^ 2))", "(letter + 1)"]);
+    }
+
+    #[test]
+    fn use_before_definition() {
+        let input = "x + a; let a = 1";
+        assert_eq!(run_script(input), Err(ScriptError::UseBeforeDefinition {
+            name: String::from("a"),
+            span: Span::new(0, 5),
+            definition: Span::new(7, 16)
+        }));
+        let error = run_script("let b = b + 1").unwrap_err();
+        assert_eq!(error.to_string(), "b is used at 0..13 before it is defined at 0..13");
+    }
+
+    #[test]
+    fn redefinition() {
+        let error = run_script("let a = 1; let a = 2").unwrap_err();
+        assert_eq!(error, ScriptError::Redefinition {
+            name: String::from("a"),
+            span: Span::new(11, 20),
+            previous: Span::new(0, 9)
+        });
+        assert_eq!(error.to_string(), "a is defined again at 11..20, it was defined at 0..9");
+    }
+}
@@ -0,0 +1,335 @@
+// Simplify an expression by rewriting it until nothing changes anymore.
+//
+// Each pass rewrites the tree bottom-up:
+//
+// * Subexpressions without variables are folded into a single
+//   rational literal, using exact arithmetic. When folding would
+//   overflow or divide by zero, the subexpression is kept as is.
+// * Sums are flattened into terms coefficient * rest. Terms with the
+//   same rest are combined, which covers x + 0, x - x and 2x + 3x.
+// * Products are flattened into a coefficient and factors base^n.
+//   Factors with the same base are combined, which covers x * 1,
+//   x * 0 and x * x^2.
+// * x^0, x^1, 1^x, x / 1 and -(-x) are removed.
+// * Functions of constants are evaluated. Square roots are written
+//   in surd form, so sqrt(8) becomes 2 * sqrt(2).
+//
+// The result has the same value as the input for all values of the
+// variables for which the input is defined. Rewrites like x * 0 => 0
+// can make the result defined for more values than the input.
+
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedMul;
+use num_traits::One;
+use num_traits::Signed;
+use num_traits::Zero;
+use crate::expression::Expression;
+use crate::expression::Operator;
+use crate::evaluate::apply;
+use crate::evaluate::checked_neg;
+use crate::evaluate::apply_function;
+use crate::surd::Surd;
+
+pub fn simplify(e: &Expression) -> Expression {
+    let mut current = rewrite(e);
+    loop {
+        let next = rewrite(&current);
+        if next == current {
+            return next;
+        }
+        current = next;
+    }
+}
+
+fn rewrite(e: &Expression) -> Expression {
+    match e {
+        Expression::Number(_) | Expression::Variable(_) => e.clone(),
+        Expression::Negation(inner) => {
+            let inner = rewrite(inner);
+            if let Some(negated) = inner.as_rational().and_then(checked_neg) {
+                return Expression::from_rational(negated);
+            }
+            match inner {
+                Expression::Negation(double) => *double,
+                other => Expression::negation(other)
+            }
+        },
+        Expression::Composite(c) => {
+            let left = rewrite(&c.left);
+            let right = rewrite(&c.right);
+            if let (Some(l), Some(r)) = (left.as_rational(), right.as_rational()) {
+                if let Some(folded) = apply(&c.operator, l, r) {
+                    return Expression::from_rational(folded);
+                }
+            }
+            let e = Expression::composite(left, c.operator.clone(), right);
+            match c.operator {
+                Operator::PLUS | Operator::MINUS => collect_sum(&e).unwrap_or(e),
+                Operator::MULT => collect_product(&e).unwrap_or(e),
+                Operator::DIV => simplify_division(e),
+                Operator::POW => simplify_power(e)
+            }
+        },
+        Expression::Call(c) => {
+            let arguments: Vec<Expression> = c.arguments.iter().map(rewrite).collect();
+            let values: Option<Vec<Surd>> = arguments.iter()
+                .map(|a| a.as_rational().map(Surd::from_rational))
+                .collect();
+            if let Some(value) = values.and_then(|v| apply_function(&c.name, &v)) {
+                return from_surd(value);
+            }
+            Expression::call(&c.name, arguments)
+        }
+    }
+}
+
+// a + b * sqrt(r) as an expression, leaving out the parts that are
+// zero or one.
+fn from_surd(s: Surd) -> Expression {
+    if s.is_rational() {
+        return Expression::from_rational(s.rational());
+    }
+    let root = Expression::call("sqrt", vec![Expression::number(s.radicand())]);
+    let root = build_product(s.coefficient(), Some(root));
+    if s.rational().is_zero() {
+        root
+    } else {
+        collect_sum(&Expression::composite(Expression::from_rational(s.rational()), Operator::PLUS, root)).unwrap()
+    }
+}
+
+fn simplify_division(e: Expression) -> Expression {
+    if let Expression::Composite(c) = e {
+        if c.right.as_rational() == Some(Rational32::one()) {
+            return *c.left;
+        }
+        return Expression::Composite(c);
+    }
+    e
+}
+
+fn simplify_power(e: Expression) -> Expression {
+    if let Expression::Composite(c) = e {
+        let exponent = c.right.as_rational();
+        if exponent == Some(Rational32::zero()) || c.left.as_rational() == Some(Rational32::one()) {
+            return Expression::number(1);
+        }
+        if exponent == Some(Rational32::one()) {
+            return *c.left;
+        }
+        return Expression::Composite(c);
+    }
+    e
+}
+
+// A term coefficient * rest of a sum, where rest is None for a constant.
+struct Term {
+    coefficient: Rational32,
+    rest: Option<Expression>
+}
+
+fn collect_sum(e: &Expression) -> Option<Expression> {
+    let mut terms: Vec<Term> = Vec::new();
+    add_terms(e, Rational32::one(), &mut terms)?;
+    let mut result: Option<Expression> = None;
+    for term in terms.into_iter().filter(|t| !t.coefficient.is_zero()) {
+        let magnitude = build_product(checked_abs(term.coefficient)?, term.rest);
+        result = Some(match result {
+            None if term.coefficient.is_negative() => Expression::negation(magnitude),
+            None => magnitude,
+            Some(sum) if term.coefficient.is_negative() => Expression::composite(sum, Operator::MINUS, magnitude),
+            Some(sum) => Expression::composite(sum, Operator::PLUS, magnitude)
+        });
+    }
+    Some(result.unwrap_or_else(|| Expression::number(0)))
+}
+
+fn add_terms(e: &Expression, sign: Rational32, terms: &mut Vec<Term>) -> Option<()> {
+    match e {
+        Expression::Composite(c) if matches!(c.operator, Operator::PLUS | Operator::MINUS) => {
+            add_terms(&c.left, sign, terms)?;
+            let right_sign = if matches!(c.operator, Operator::MINUS) { -sign } else { sign };
+            add_terms(&c.right, right_sign, terms)
+        },
+        Expression::Negation(inner) => add_terms(inner, -sign, terms),
+        _ => {
+            let (coefficient, rest) = split_coefficient(e)?;
+            let coefficient = coefficient.checked_mul(&sign)?;
+            match terms.iter_mut().find(|t| t.rest == rest) {
+                Some(term) => term.coefficient = term.coefficient.checked_add(&coefficient)?,
+                None => terms.push(Term{coefficient, rest})
+            }
+            Some(())
+        }
+    }
+}
+
+fn checked_abs(r: Rational32) -> Option<Rational32> {
+    if r.is_negative() {
+        checked_neg(r)
+    } else {
+        Some(r)
+    }
+}
+
+fn collect_product(e: &Expression) -> Option<Expression> {
+    let (coefficient, rest) = split_coefficient(e)?;
+    Some(build_product(coefficient, rest))
+}
+
+fn build_product(coefficient: Rational32, rest: Option<Expression>) -> Expression {
+    match rest {
+        _ if coefficient.is_zero() => Expression::number(0),
+        None => Expression::from_rational(coefficient),
+        Some(rest) if coefficient.is_one() => rest,
+        Some(rest) if coefficient == -Rational32::one() => Expression::negation(rest),
+        Some(rest) => Expression::composite(Expression::from_rational(coefficient), Operator::MULT, rest)
+    }
+}
+
+// A factor base^exponent of a product.
+struct Factor {
+    base: Expression,
+    exponent: i32
+}
+
+// Split a product into its constant coefficient and the product of the
+// remaining factors, with equal bases combined into a single power.
+fn split_coefficient(e: &Expression) -> Option<(Rational32, Option<Expression>)> {
+    let mut coefficient = Rational32::one();
+    let mut factors: Vec<Factor> = Vec::new();
+    multiply_factors(e, &mut coefficient, &mut factors)?;
+    let mut rest: Option<Expression> = None;
+    for factor in factors.into_iter().filter(|f| f.exponent != 0) {
+        let power = if factor.exponent == 1 {
+            factor.base
+        } else {
+            Expression::composite(factor.base, Operator::POW, Expression::from_rational(Rational32::from(factor.exponent)))
+        };
+        rest = Some(match rest {
+            None => power,
+            Some(product) => Expression::composite(product, Operator::MULT, power)
+        });
+    }
+    Some((coefficient, rest))
+}
+
+fn multiply_factors(e: &Expression, coefficient: &mut Rational32, factors: &mut Vec<Factor>) -> Option<()> {
+    if let Some(r) = e.as_rational() {
+        *coefficient = coefficient.checked_mul(&r)?;
+        return Some(());
+    }
+    match e {
+        Expression::Composite(c) if matches!(c.operator, Operator::MULT) => {
+            multiply_factors(&c.left, coefficient, factors)?;
+            multiply_factors(&c.right, coefficient, factors)
+        },
+        Expression::Negation(inner) => {
+            *coefficient = checked_neg(*coefficient)?;
+            multiply_factors(inner, coefficient, factors)
+        },
+        _ => {
+            let (base, exponent) = match e {
+                Expression::Composite(c) if matches!(c.operator, Operator::POW) => {
+                    match c.right.as_rational() {
+                        Some(n) if n.is_integer() => ((*c.left).clone(), *n.numer()),
+                        _ => (e.clone(), 1)
+                    }
+                },
+                _ => (e.clone(), 1)
+            };
+            match factors.iter_mut().find(|f| f.base == base) {
+                Some(factor) => factor.exponent = factor.exponent.checked_add(exponent)?,
+                None => factors.push(Factor{base, exponent})
+            }
+            Some(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use ::num_rational::Rational32;
+    use super::simplify;
+    use crate::lang::ExprParser;
+    use crate::expression::Expression;
+    use crate::evaluate::evaluate;
+
+    fn simplify_and_expect(input: &str, expected: &str) {
+        let e = ExprParser::new().parse(input).unwrap();
+        let simplified = simplify(&e);
+        assert_eq!(simplified.to_string(), expected);
+        assert_same_values(&e, &simplified);
+        assert_eq!(simplify(&simplified), simplified);
+    }
+
+    fn assert_same_values(original: &Expression, simplified: &Expression) {
+        for xn in -4 ..= 4 {
+            for yn in -3 ..= 3 {
+                let mut values = HashMap::new();
+                values.insert(String::from("x"), Rational32::new(xn, 2));
+                values.insert(String::from("y"), Rational32::new(yn, 3));
+                if let Some(expected) = evaluate(original, &values) {
+                    assert_eq!(evaluate(simplified, &values), Some(expected));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constant_folding() {
+        simplify_and_expect("1/2 + 1/3", "(5 / 6)");
+        simplify_and_expect("2^-2*3", "(3 / 4)");
+        simplify_and_expect("1 - 4", "(-3)");
+    }
+
+    #[test]
+    fn identities() {
+        simplify_and_expect("x*1", "x");
+        simplify_and_expect("1*x", "x");
+        simplify_and_expect("x+0", "x");
+        simplify_and_expect("0+x", "x");
+        simplify_and_expect("x*0", "0");
+        simplify_and_expect("x-x", "0");
+        simplify_and_expect("x/1", "x");
+        simplify_and_expect("x^1 + y^0", "(x + 1)");
+        simplify_and_expect("--x", "x");
+    }
+
+    #[test]
+    fn like_terms() {
+        simplify_and_expect("2*x + 3*x", "(5 * x)");
+        simplify_and_expect("x + y - x + 1/2*y", "((3 / 2) * y)");
+        simplify_and_expect("x*y - 1 + 2*x*y + 3", "((3 * (x * y)) + 2)");
+        simplify_and_expect("x - 2*x", "(-x)");
+    }
+
+    #[test]
+    fn powers() {
+        simplify_and_expect("x*x", "(x ^ 2)");
+        simplify_and_expect("x^2*y*x^3*2", "(2 * ((x ^ 5) * y))");
+        simplify_and_expect("x^2*x^-2 + y", "(1 + y)");
+        simplify_and_expect("x*x^-3", "(x ^ (-2))");
+    }
+
+    #[test]
+    fn nested() {
+        simplify_and_expect("(x + 1)*(x + 1) - (1 + x)^2", "(((x + 1) ^ 2) - ((1 + x) ^ 2))");
+        simplify_and_expect("3*(x*0 + 2*y) / (y + y)", "((6 * y) / (2 * y))");
+    }
+
+    #[test]
+    fn functions() {
+        simplify_and_expect("sqrt(8) + 1", "((2 * sqrt(2)) + 1)");
+        simplify_and_expect("sqrt(9/4) - abs(-2) + sq(1/2)", "(-(1 / 4))");
+        simplify_and_expect("sqrt(x*x) + sqrt(x^2)", "(2 * sqrt((x ^ 2)))");
+        simplify_and_expect("sqrt(-1)", "sqrt((-1))");
+    }
+
+    #[test]
+    fn folding_keeps_division_by_zero() {
+        simplify_and_expect("1/0 + x", "((1 / 0) + x)");
+    }
+}
@@ -0,0 +1,310 @@
+// The rigid motion that brings a conic to standard form
+//
+//     x'^2/a^2 + y'^2/b^2 = 1    ellipse, a >= b
+//     x'^2/a^2 - y'^2/b^2 = 1    hyperbola
+//     y'^2 = 4 p x'              parabola, p > 0
+//
+// The new coordinates are related to the old ones by
+//
+//     x = h + x' cos - y' sin
+//     y = k + x' sin + y' cos
+//
+// where (h, k) is the center, or the vertex of a parabola.
+//
+// Central conics are translated first. The center and the constant
+// term are rational, and the new quadratic coefficients are the
+// eigenvalues ((A + C) +- sqrt((A - C)^2 + B^2)) / 2 of the quadratic
+// part, so a^2 and b^2 are quadratic surds. cos and sin of the angle
+// are only quadratic surds when the square root in the eigenvalues is
+// rational; otherwise they are approximated. For a parabola that root
+// is always rational, because B^2 = 4AC, and everything is exact.
+
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::Signed;
+use num_traits::ToPrimitive;
+use num_traits::Zero;
+use std::cmp::Ordering;
+use crate::conic::Conic;
+use crate::conic::ConicKind;
+use crate::evaluate::checked_neg;
+use crate::surd::Real;
+use crate::surd::Surd;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Ellipse{a_squared: Surd, b_squared: Surd},
+    Hyperbola{a_squared: Surd, b_squared: Surd},
+    Parabola{p: Surd}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation {
+    pub cos: Real,
+    pub sin: Real
+}
+
+impl Rotation {
+    // In radians, between -pi and pi.
+    pub fn angle(&self) -> f64 {
+        self.sin.to_f64().atan2(self.cos.to_f64())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StandardForm {
+    pub shape: Shape,
+    pub rotation: Rotation,
+    // (h, k), the origin of the new coordinates.
+    pub origin: (Surd, Surd),
+    // A', B', C', D', E', F' of the conic in the new coordinates,
+    // before dividing by the constant term. Only A', C' and F' are
+    // nonzero for a central conic and only C' and D' for a parabola.
+    pub coefficients: [Surd; 6]
+}
+
+// None for degenerate conics and on overflow.
+pub fn standard_form(conic: &Conic) -> Option<StandardForm> {
+    match conic.kind()? {
+        ConicKind::Ellipse | ConicKind::Hyperbola => central_standard_form(conic),
+        ConicKind::Parabola => parabola_standard_form(conic),
+        _ => None
+    }
+}
+
+fn central_standard_form(conic: &Conic) -> Option<StandardForm> {
+    let Conic{a, b, c, d, e, f} = *conic;
+    let two = Rational32::from(2);
+    // The center solves 2Ah + Bk + D = 0 and Bh + 2Ck + E = 0.
+    let det = Rational32::from(4).checked_mul(&conic.discriminant()?)?;
+    let h = b.checked_mul(&e)?.checked_sub(&two.checked_mul(&c)?.checked_mul(&d)?)?.checked_div(&det)?;
+    let k = b.checked_mul(&d)?.checked_sub(&two.checked_mul(&a)?.checked_mul(&e)?)?.checked_div(&det)?;
+    let constant = f.checked_add(&d.checked_mul(&h)?.checked_add(&e.checked_mul(&k)?)?.checked_div(&two)?)?;
+
+    let (smaller, larger) = eigenvalues(conic)?;
+    let ellipse = conic.kind()? == ConicKind::Ellipse;
+    // The eigenvalue along x': the one of smaller magnitude for an
+    // ellipse, so that a >= b, and the one with the sign opposite to
+    // the constant for a hyperbola.
+    let (first, second) = if ellipse {
+        if a.checked_add(&c)?.is_positive() { (smaller, larger) } else { (larger, smaller) }
+    } else if constant.is_negative() {
+        (larger, smaller)
+    } else {
+        (smaller, larger)
+    };
+
+    let minus_constant = Surd::from_rational(checked_neg(constant)?);
+    let a_squared = minus_constant.checked_div(&first)?;
+    let b_squared = minus_constant.checked_div(&second)?;
+    let shape = if ellipse {
+        Shape::Ellipse{a_squared, b_squared}
+    } else {
+        Shape::Hyperbola{a_squared, b_squared: b_squared.checked_neg()?}
+    };
+    let zero = Surd::zero();
+    Some(StandardForm {
+        shape,
+        rotation: axis_rotation(conic, &first)?,
+        origin: (Surd::from_rational(h), Surd::from_rational(k)),
+        coefficients: [first, zero, second, zero, zero, Surd::from_rational(constant)]
+    })
+}
+
+// ((A + C) - R) / 2 and ((A + C) + R) / 2 with R = sqrt((A - C)^2 + B^2).
+fn eigenvalues(conic: &Conic) -> Option<(Surd, Surd)> {
+    let half = Surd::from_rational(Rational32::new(1, 2));
+    let difference = conic.a.checked_sub(&conic.c)?;
+    let root = Surd::sqrt(difference.checked_mul(&difference)?.checked_add(&conic.b.checked_mul(&conic.b)?)?)?;
+    let trace = Surd::from_rational(conic.a.checked_add(&conic.c)?);
+    Some((trace.checked_sub(&root)?.checked_mul(&half)?, trace.checked_add(&root)?.checked_mul(&half)?))
+}
+
+// The rotation whose x' axis is the eigenvector (B/2, lambda - A) of
+// the quadratic part, turned so that cos > 0, or sin = 1 for a quarter
+// turn.
+fn axis_rotation(conic: &Conic, eigenvalue: &Surd) -> Option<Rotation> {
+    if conic.b.is_zero() {
+        let along_x = *eigenvalue == Surd::from_rational(conic.a);
+        let (cos, sin) = if along_x { (Surd::one(), Surd::zero()) } else { (Surd::zero(), Surd::one()) };
+        return Some(Rotation{cos: Real::Exact(cos), sin: Real::Exact(sin)});
+    }
+    let sign = if conic.b.is_negative() { Rational32::from(-1) } else { Rational32::from(1) };
+    let x = conic.b.checked_mul(&sign)?.checked_div(&Rational32::from(2))?;
+    let y = eigenvalue.checked_sub(&Surd::from_rational(conic.a))?.checked_mul(&Surd::from_rational(sign))?;
+    if let Some(y) = y.to_rational() {
+        return unit_rotation(x, y);
+    }
+    let (x, y) = (x.to_f64()?, y.to_f64());
+    let norm = x.hypot(y);
+    Some(Rotation{cos: Real::Approximate(x / norm), sin: Real::Approximate(y / norm)})
+}
+
+// The rotation taking (1, 0) to the direction of (x, y).
+fn unit_rotation(x: Rational32, y: Rational32) -> Option<Rotation> {
+    let norm = Surd::sqrt(x.checked_mul(&x)?.checked_add(&y.checked_mul(&y)?)?)?;
+    Some(Rotation {
+        cos: Real::Exact(Surd::from_rational(x).checked_div(&norm)?),
+        sin: Real::Exact(Surd::from_rational(y).checked_div(&norm)?)
+    })
+}
+
+// The x' axis is the axis of symmetry, the null direction of the
+// quadratic part, pointing into the parabola. With lambda = A + C the
+// equation becomes lambda y'^2 + D' x' + E' y' + F = 0, and completing
+// the square gives the vertex and 4p = -D' / lambda.
+fn parabola_standard_form(conic: &Conic) -> Option<StandardForm> {
+    let Conic{a, b, c, d, e, f} = *conic;
+    let half_b = b.checked_div(&Rational32::from(2))?;
+    let (x, y) = if c.is_zero() && b.is_zero() { (-half_b, a) } else { (c, -half_b) };
+    let rotation = unit_rotation(x, y)?;
+    let (mut cos, mut sin) = (rotation.cos.exact()?, rotation.sin.exact()?);
+    let d = Surd::from_rational(d);
+    let e = Surd::from_rational(e);
+    let lambda = Surd::from_rational(a.checked_add(&c)?);
+    let mut d_new = d.checked_mul(&cos)?.checked_add(&e.checked_mul(&sin)?)?;
+    if d_new.checked_div(&lambda)?.signum() == Ordering::Greater {
+        cos = cos.checked_neg()?;
+        sin = sin.checked_neg()?;
+        d_new = d_new.checked_neg()?;
+    }
+    let e_new = e.checked_mul(&cos)?.checked_sub(&d.checked_mul(&sin)?)?;
+    let two_lambda = lambda.checked_add(&lambda)?;
+    let y0 = e_new.checked_neg()?.checked_div(&two_lambda)?;
+    let square = e_new.checked_mul(&e_new)?.checked_div(&two_lambda.checked_add(&two_lambda)?)?;
+    let x0 = Surd::from_rational(f).checked_sub(&square)?.checked_neg()?.checked_div(&d_new)?;
+    let p = d_new.checked_neg()?.checked_div(&two_lambda.checked_add(&two_lambda)?)?;
+    let h = x0.checked_mul(&cos)?.checked_sub(&y0.checked_mul(&sin)?)?;
+    let k = x0.checked_mul(&sin)?.checked_add(&y0.checked_mul(&cos)?)?;
+    let zero = Surd::zero();
+    Some(StandardForm {
+        shape: Shape::Parabola{p},
+        rotation: Rotation{cos: Real::Exact(cos), sin: Real::Exact(sin)},
+        origin: (h, k),
+        coefficients: [zero, zero, lambda, d_new, zero, zero]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::EquationParser;
+
+    fn standard(input: &str) -> Option<StandardForm> {
+        standard_form(&Conic::from_equation(&EquationParser::new().parse(input).unwrap()).unwrap())
+    }
+
+    fn strings(values: &[Surd]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn exact(r: Real) -> String {
+        r.exact().unwrap().to_string()
+    }
+
+    #[test]
+    fn axis_parallel_ellipse() {
+        let form = standard("(x - 1)^2/4 + (y + 2)^2/9 = 1").unwrap();
+        assert_eq!(form.shape, Shape::Ellipse {
+            a_squared: Surd::from_rational(Rational32::from(9)),
+            b_squared: Surd::from_rational(Rational32::from(4))
+        });
+        assert_eq!(strings(&[form.origin.0, form.origin.1]), vec!["1", "-2"]);
+        assert_eq!((exact(form.rotation.cos), exact(form.rotation.sin)), (String::from("0"), String::from("1")));
+    }
+
+    #[test]
+    fn rotated_ellipse() {
+        let form = standard("5*x^2 + 4*x*y + 8*y^2 = 36").unwrap();
+        match form.shape {
+            Shape::Ellipse{a_squared, b_squared} => assert_eq!(strings(&[a_squared, b_squared]), vec!["9", "4"]),
+            other => panic!("Expected an ellipse, got {:?}", other)
+        }
+        assert_eq!(exact(form.rotation.cos), "2/5*sqrt(5)");
+        assert_eq!(exact(form.rotation.sin), "-1/5*sqrt(5)");
+        assert_eq!(strings(&form.coefficients), vec!["4", "0", "9", "0", "0", "-36"]);
+    }
+
+    #[test]
+    fn negative_ellipse() {
+        let form = standard("-x^2 - 4*y^2 + 4 = 0").unwrap();
+        match form.shape {
+            Shape::Ellipse{a_squared, b_squared} => assert_eq!(strings(&[a_squared, b_squared]), vec!["4", "1"]),
+            other => panic!("Expected an ellipse, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn rectangular_hyperbola() {
+        let form = standard("x*y = 1").unwrap();
+        match form.shape {
+            Shape::Hyperbola{a_squared, b_squared} => assert_eq!(strings(&[a_squared, b_squared]), vec!["2", "2"]),
+            other => panic!("Expected a hyperbola, got {:?}", other)
+        }
+        assert_eq!(exact(form.rotation.cos), "1/2*sqrt(2)");
+        assert_eq!(exact(form.rotation.sin), "1/2*sqrt(2)");
+        assert!((form.rotation.angle() - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hyperbola_along_y() {
+        let form = standard("x^2 - y^2 = -1").unwrap();
+        match form.shape {
+            Shape::Hyperbola{a_squared, b_squared} => assert_eq!(strings(&[a_squared, b_squared]), vec!["1", "1"]),
+            other => panic!("Expected a hyperbola, got {:?}", other)
+        }
+        assert_eq!(exact(form.rotation.sin), "1");
+    }
+
+    #[test]
+    fn irrational_rotation() {
+        let form = standard("x^2 + x*y + 2*y^2 = 1").unwrap();
+        match form.shape {
+            Shape::Ellipse{a_squared, b_squared} => {
+                assert_eq!(strings(&[a_squared, b_squared]), vec!["6/7 + 2/7*sqrt(2)", "6/7 - 2/7*sqrt(2)"]);
+            },
+            other => panic!("Expected an ellipse, got {:?}", other)
+        }
+        assert!(matches!(form.rotation.cos, Real::Approximate(_)));
+        let (cos, sin) = (form.rotation.cos.to_f64(), form.rotation.sin.to_f64());
+        assert!((cos * cos + sin * sin - 1.0).abs() < 1e-12);
+        // The new x' axis is an eigenvector for the smaller eigenvalue.
+        let lambda = (3.0 - 2f64.sqrt()) / 2.0;
+        assert!((cos + 0.5 * sin - lambda * cos).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parabola() {
+        let form = standard("y = x^2").unwrap();
+        assert_eq!(form.shape, Shape::Parabola{p: Surd::from_rational(Rational32::new(1, 4))});
+        assert_eq!((exact(form.rotation.cos), exact(form.rotation.sin)), (String::from("0"), String::from("1")));
+        assert_eq!(strings(&[form.origin.0, form.origin.1]), vec!["0", "0"]);
+
+        let form = standard("(y - 1)^2 = -8*(x - 3)").unwrap();
+        assert_eq!(form.shape, Shape::Parabola{p: Surd::from_rational(Rational32::from(2))});
+        assert_eq!(exact(form.rotation.cos), "-1");
+        assert_eq!(strings(&[form.origin.0, form.origin.1]), vec!["3", "1"]);
+    }
+
+    #[test]
+    fn rotated_parabola() {
+        let form = standard("x^2 - 2*x*y + y^2 = x + y").unwrap();
+        match form.shape {
+            Shape::Parabola{p} => assert_eq!(p.to_string(), "1/8*sqrt(2)"),
+            other => panic!("Expected a parabola, got {:?}", other)
+        }
+        assert_eq!(exact(form.rotation.cos), "1/2*sqrt(2)");
+        assert_eq!(exact(form.rotation.sin), "1/2*sqrt(2)");
+        assert_eq!(strings(&[form.origin.0, form.origin.1]), vec!["0", "0"]);
+    }
+
+    #[test]
+    fn degenerate() {
+        assert_eq!(standard("x^2 - y^2 = 0"), None);
+        assert_eq!(standard("x^2 + y^2 = -1"), None);
+        assert_eq!(standard("x + y = 1"), None);
+    }
+}
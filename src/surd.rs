@@ -0,0 +1,489 @@
+// Exact real numbers of the form a + b * sqrt(r), with rational a and
+// b and a square-free natural number r.
+//
+// The radicand is kept square-free through sqrt::sqrt, so every number
+// has a single representation: when b is zero, r is 1 and when r is 1,
+// b is zero. Sums and products are only defined when both operands
+// have the same radicand or when one of them is rational. All
+// arithmetic is checked and returns None when the result cannot be
+// represented or overflows.
+
+use std::cmp::Ordering;
+use std::ops::Add;
+use std::ops::Div;
+use std::ops::Mul;
+use std::ops::Neg;
+use std::ops::Sub;
+use ::num_rational::Ratio;
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::CheckedSub;
+use num_traits::One;
+use num_traits::Signed;
+use num_traits::ToPrimitive;
+use num_traits::Zero;
+use crate::evaluate::checked_neg;
+use crate::sqrt::sqrt;
+
+// A real number that is exact when it is a surd and approximate
+// otherwise, for results like cos and sin of a rotation that are
+// only sometimes quadratic surds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Real {
+    Exact(Surd),
+    Approximate(f64)
+}
+
+impl Real {
+    pub fn exact(self) -> Option<Surd> {
+        match self {
+            Real::Exact(s) => Some(s),
+            Real::Approximate(_) => None
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Real::Exact(s) => s.to_f64(),
+            Real::Approximate(x) => x
+        }
+    }
+
+    pub fn is_exact(self) -> bool {
+        matches!(self, Real::Exact(_))
+    }
+
+    // Exact as q*sqrt(r) for a non-negative rational, approximate for an
+    // irrational surd and on overflow.
+    pub fn sqrt(self) -> Real {
+        match self.exact().and_then(|s| s.checked_sqrt()) {
+            Some(root) => Real::Exact(root),
+            None => Real::Approximate(self.to_f64().sqrt())
+        }
+    }
+
+    // The operators are exact when both operands are and the surd
+    // arithmetic succeeds, and approximate otherwise.
+    fn combine<E, A>(self, other: Real, exact: E, approximate: A) -> Real
+            where E: Fn(&Surd, &Surd) -> Option<Surd>, A: Fn(f64, f64) -> f64 {
+        if let (Real::Exact(first), Real::Exact(second)) = (self, other) {
+            if let Some(result) = exact(&first, &second) {
+                return Real::Exact(result);
+            }
+        }
+        Real::Approximate(approximate(self.to_f64(), other.to_f64()))
+    }
+}
+
+impl From<Surd> for Real {
+    fn from(s: Surd) -> Real {
+        Real::Exact(s)
+    }
+}
+
+impl Add for Real {
+    type Output = Real;
+
+    fn add(self, other: Real) -> Real {
+        self.combine(other, Surd::checked_add, |x, y| x + y)
+    }
+}
+
+impl Sub for Real {
+    type Output = Real;
+
+    fn sub(self, other: Real) -> Real {
+        self.combine(other, Surd::checked_sub, |x, y| x - y)
+    }
+}
+
+impl Mul for Real {
+    type Output = Real;
+
+    fn mul(self, other: Real) -> Real {
+        self.combine(other, Surd::checked_mul, |x, y| x * y)
+    }
+}
+
+impl Div for Real {
+    type Output = Real;
+
+    fn div(self, other: Real) -> Real {
+        self.combine(other, Surd::checked_div, |x, y| x / y)
+    }
+}
+
+impl Neg for Real {
+    type Output = Real;
+
+    fn neg(self) -> Real {
+        match self {
+            Real::Exact(s) => match s.checked_neg() {
+                Some(negated) => Real::Exact(negated),
+                None => Real::Approximate(-s.to_f64())
+            },
+            Real::Approximate(x) => Real::Approximate(-x)
+        }
+    }
+}
+
+impl ToString for Real {
+    fn to_string(&self) -> String {
+        match self {
+            Real::Exact(s) => s.to_string(),
+            Real::Approximate(x) => format!("~{}", x)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Surd {
+    rational: Rational32,
+    coefficient: Rational32,
+    radicand: u32
+}
+
+impl Surd {
+    // a + b * sqrt(r) for any natural number r.
+    pub fn new(rational: Rational32, coefficient: Rational32, radicand: u32) -> Option<Surd> {
+        if coefficient.is_zero() || radicand == 0 {
+            return Some(Surd::from_rational(rational));
+        }
+        let (square, root) = sqrt(Rational32::from(i32::try_from(radicand).ok()?))?;
+        let coefficient = coefficient.checked_mul(&square)?;
+        if root == 1 {
+            return Some(Surd::from_rational(rational.checked_add(&coefficient)?));
+        }
+        Some(Surd{rational, coefficient, radicand: root})
+    }
+
+    pub fn from_rational(r: Rational32) -> Surd {
+        Surd{rational: r, coefficient: Rational32::zero(), radicand: 1}
+    }
+
+    pub fn zero() -> Surd {
+        Surd::from_rational(Rational32::zero())
+    }
+
+    pub fn one() -> Surd {
+        Surd::from_rational(Rational32::one())
+    }
+
+    // The square root of a non-negative rational.
+    pub fn sqrt(r: Rational32) -> Option<Surd> {
+        if r.is_negative() {
+            return None;
+        }
+        let (coefficient, radicand) = sqrt(r)?;
+        Surd::new(Rational32::zero(), coefficient, radicand)
+    }
+
+    pub fn rational(&self) -> Rational32 {
+        self.rational
+    }
+
+    pub fn coefficient(&self) -> Rational32 {
+        self.coefficient
+    }
+
+    pub fn radicand(&self) -> u32 {
+        self.radicand
+    }
+
+    pub fn is_rational(&self) -> bool {
+        self.coefficient.is_zero()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.is_rational() && self.rational.is_zero()
+    }
+
+    pub fn to_rational(self) -> Option<Rational32> {
+        if self.is_rational() {
+            Some(self.rational)
+        } else {
+            None
+        }
+    }
+
+    // The pair (q, r) meaning q * sqrt(r), as returned by sqrt::sqrt,
+    // when there is no rational part.
+    pub fn to_root(self) -> Option<(Rational32, u32)> {
+        if self.rational.is_zero() {
+            Some((self.coefficient, self.radicand))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        let rational = self.rational.to_f64().unwrap();
+        let coefficient = self.coefficient.to_f64().unwrap();
+        rational + coefficient * (self.radicand as f64).sqrt()
+    }
+
+    // The squares of 32 bit parts cannot overflow 128 bits.
+    pub fn signum(&self) -> Ordering {
+        sign_of(wide(self.rational), wide(self.coefficient), i128::from(self.radicand)).unwrap()
+    }
+
+    // Exact comparison in 128 bit integers, None on overflow. With
+    // different radicands the difference is a + b sqrt(r) + c sqrt(s).
+    // Its last two terms have the sign u of b r + c sqrt(rs), and when
+    // a has the other sign the larger of u^2 and a^2 decides, which is
+    // the sign of b^2 r + c^2 s - a^2 + 2bc sqrt(rs).
+    pub fn checked_cmp(&self, other: &Surd) -> Option<Ordering> {
+        let a = wide(self.rational).checked_sub(&wide(other.rational))?;
+        if self.radicand == other.radicand || self.is_rational() || other.is_rational() {
+            let b = wide(self.coefficient).checked_sub(&wide(other.coefficient))?;
+            return sign_of(a, b, i128::from(self.radicand.max(other.radicand)));
+        }
+        let (b, c) = (wide(self.coefficient), -wide(other.coefficient));
+        let (r, s) = (i128::from(self.radicand), i128::from(other.radicand));
+        let rs = r.checked_mul(s)?;
+        let u = sign_of(b.checked_mul(&Ratio::from(r))?, c, rs)?;
+        let sign_a = a.numer().cmp(&0);
+        if sign_a == Ordering::Equal || sign_a == u {
+            return Some(u);
+        }
+        let squares = b.checked_mul(&b)?.checked_mul(&Ratio::from(r))?
+            .checked_add(&c.checked_mul(&c)?.checked_mul(&Ratio::from(s))?)?
+            .checked_sub(&a.checked_mul(&a)?)?;
+        let product = b.checked_mul(&c)?.checked_mul(&Ratio::from(2))?;
+        Some(match sign_of(squares, product, rs)? {
+            Ordering::Greater => u,
+            Ordering::Less => sign_a,
+            Ordering::Equal => Ordering::Equal
+        })
+    }
+
+    pub fn checked_neg(&self) -> Option<Surd> {
+        Some(Surd {
+            rational: checked_neg(self.rational)?,
+            coefficient: checked_neg(self.coefficient)?,
+            radicand: self.radicand
+        })
+    }
+
+    pub fn checked_abs(&self) -> Option<Surd> {
+        if self.signum() == Ordering::Less {
+            self.checked_neg()
+        } else {
+            Some(*self)
+        }
+    }
+
+    // a - b * sqrt(r)
+    pub fn conjugate(&self) -> Option<Surd> {
+        Some(Surd{coefficient: checked_neg(self.coefficient)?, ..*self})
+    }
+
+    pub fn checked_add(&self, other: &Surd) -> Option<Surd> {
+        let radicand = common_radicand(self, other)?;
+        Surd::new(
+            self.rational.checked_add(&other.rational)?,
+            self.coefficient.checked_add(&other.coefficient)?,
+            radicand)
+    }
+
+    pub fn checked_sub(&self, other: &Surd) -> Option<Surd> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    // (a + b sqrt(r)) (c + d sqrt(r)) = ac + bdr + (ad + bc) sqrt(r)
+    pub fn checked_mul(&self, other: &Surd) -> Option<Surd> {
+        let radicand = common_radicand(self, other)?;
+        let r = Rational32::from(i32::try_from(radicand).ok()?);
+        let bd = self.coefficient.checked_mul(&other.coefficient)?;
+        let rational = self.rational.checked_mul(&other.rational)?.checked_add(&bd.checked_mul(&r)?)?;
+        let ad = self.rational.checked_mul(&other.coefficient)?;
+        let bc = self.coefficient.checked_mul(&other.rational)?;
+        Surd::new(rational, ad.checked_add(&bc)?, radicand)
+    }
+
+    // Multiply by the conjugate of the divisor, which makes it rational.
+    // A rational divisor divides both parts directly, since squaring it
+    // for the norm overflows much earlier.
+    pub fn checked_div(&self, other: &Surd) -> Option<Surd> {
+        if other.is_rational() {
+            if other.rational.is_zero() {
+                return None;
+            }
+            return Surd::new(
+                self.rational.checked_div(&other.rational)?,
+                self.coefficient.checked_div(&other.rational)?,
+                self.radicand);
+        }
+        let conjugate = other.conjugate()?;
+        let norm = other.checked_mul(&conjugate)?.to_rational()?;
+        if norm.is_zero() {
+            return None;
+        }
+        let numerator = self.checked_mul(&conjugate)?;
+        Surd::new(
+            numerator.rational.checked_div(&norm)?,
+            numerator.coefficient.checked_div(&norm)?,
+            numerator.radicand)
+    }
+
+    pub fn checked_pow(&self, exponent: i32) -> Option<Surd> {
+        if exponent < 0 {
+            return Surd::one().checked_div(&self.checked_pow(exponent.checked_neg()?)?);
+        }
+        let mut result = Surd::one();
+        let mut square = *self;
+        let mut remaining = exponent as u32;
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                square = square.checked_mul(&square)?;
+            }
+        }
+        Some(result)
+    }
+
+    // Only rational numbers have a square root of this form in general.
+    pub fn checked_sqrt(&self) -> Option<Surd> {
+        Surd::sqrt(self.to_rational()?)
+    }
+}
+
+fn common_radicand(first: &Surd, second: &Surd) -> Option<u32> {
+    if first.is_rational() {
+        Some(second.radicand)
+    } else if second.is_rational() || first.radicand == second.radicand {
+        Some(first.radicand)
+    } else {
+        None
+    }
+}
+
+// Surds are only partially ordered: the comparison is exact, and
+// numbers whose comparison overflows are not comparable.
+impl PartialOrd for Surd {
+    fn partial_cmp(&self, other: &Surd) -> Option<Ordering> {
+        self.checked_cmp(other)
+    }
+}
+
+// The sign of a + b sqrt(r), which follows from the signs of a and b,
+// and when they differ from comparing a^2 with b^2 r. The radicand does
+// not have to be square-free.
+fn sign_of(a: Ratio<i128>, b: Ratio<i128>, r: i128) -> Option<Ordering> {
+    let sign_a = a.numer().cmp(&0);
+    let sign_b = b.numer().cmp(&0);
+    if sign_a == Ordering::Equal {
+        return Some(sign_b);
+    }
+    if sign_b == Ordering::Equal || sign_a == sign_b {
+        return Some(sign_a);
+    }
+    let a_squared = a.checked_mul(&a)?;
+    let b_squared = b.checked_mul(&b)?.checked_mul(&Ratio::from(r))?;
+    Some(match a_squared.cmp(&b_squared) {
+        Ordering::Greater => sign_a,
+        Ordering::Less => sign_b,
+        Ordering::Equal => Ordering::Equal
+    })
+}
+
+fn wide(r: Rational32) -> Ratio<i128> {
+    Ratio::new_raw(i128::from(*r.numer()), i128::from(*r.denom()))
+}
+
+impl ToString for Surd {
+    fn to_string(&self) -> String {
+        if self.is_rational() {
+            return self.rational.to_string();
+        }
+        let root = if self.coefficient.is_one() {
+            format!("sqrt({})", self.radicand)
+        } else if self.coefficient == -Rational32::one() {
+            format!("-sqrt({})", self.radicand)
+        } else {
+            format!("{}*sqrt({})", self.coefficient, self.radicand)
+        };
+        if self.rational.is_zero() {
+            root
+        } else if let Some(stripped) = root.strip_prefix('-') {
+            format!("{} - {}", self.rational, stripped)
+        } else {
+            format!("{} + {}", self.rational, root)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn r(n: i32, d: i32) -> Rational32 {
+        Rational32::new(n, d)
+    }
+
+    #[test]
+    fn normalized() {
+        let s = Surd::new(r(1, 1), r(1, 2), 12).unwrap();
+        assert_eq!((s.rational(), s.coefficient(), s.radicand()), (r(1, 1), r(1, 1), 3));
+        assert_eq!(Surd::new(r(1, 1), r(1, 2), 16), Some(Surd::from_rational(r(3, 1))));
+        assert_eq!(Surd::sqrt(r(27, 16)).unwrap().to_root(), Some((r(3, 4), 3)));
+        assert_eq!(Surd::sqrt(r(-1, 1)), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let s = Surd::new(r(1, 1), r(1, 1), 2).unwrap();
+        let t = Surd::new(r(1, 1), r(-1, 1), 2).unwrap();
+        assert_eq!(s.checked_mul(&t), Some(Surd::from_rational(r(-1, 1))));
+        assert_eq!(s.checked_add(&t), Some(Surd::from_rational(r(2, 1))));
+        assert_eq!(s.checked_pow(2).unwrap().to_string(), "3 + 2*sqrt(2)");
+        assert_eq!(Surd::one().checked_div(&s).unwrap().to_string(), "-1 + sqrt(2)");
+        assert_eq!(s.checked_add(&Surd::sqrt(r(3, 1)).unwrap()), None);
+        assert_eq!(Surd::sqrt(r(2, 1)).unwrap().checked_mul(&Surd::sqrt(r(3, 1)).unwrap()), None);
+    }
+
+    #[test]
+    fn compare() {
+        let sqrt2 = Surd::sqrt(r(2, 1)).unwrap();
+        assert!(sqrt2 > Surd::from_rational(r(7, 5)));
+        assert!(sqrt2 < Surd::from_rational(r(3, 2)));
+        assert_eq!(Surd::new(r(3, 2), r(-1, 1), 2).unwrap().signum(), Ordering::Greater);
+        assert_eq!(Surd::new(r(-3, 2), r(1, 1), 2).unwrap().signum(), Ordering::Less);
+        assert!(sqrt2 < Surd::sqrt(r(3, 1)).unwrap());
+        // 1 + sqrt(2) - sqrt(6) is about -0.035.
+        assert!(sqrt2 < Surd::new(r(-1, 1), r(1, 1), 6).unwrap());
+        assert!(Surd::new(r(1, 1), r(1, 1), 2).unwrap() > Surd::sqrt(r(5, 1)).unwrap());
+        // Differences that do not fit 32 bits.
+        let first = Surd::from_rational(r(2147483646, 2147483647));
+        let second = Surd::from_rational(r(2147483645, 2147483646));
+        assert_eq!(first.partial_cmp(&second), Some(Ordering::Greater));
+        assert_eq!(second.partial_cmp(&first), Some(Ordering::Less));
+        let first = Surd::new(r(1, 2147483647), r(1, 2147483646), 7).unwrap();
+        assert_eq!(first.partial_cmp(&Surd::new(r(1, 2147483647), r(1, 2147483645), 7).unwrap()), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn real() {
+        let sqrt2 = Real::from(Surd::sqrt(r(2, 1)).unwrap());
+        let sqrt3 = Real::from(Surd::sqrt(r(3, 1)).unwrap());
+        assert_eq!((sqrt2 * sqrt2).to_string(), "2");
+        assert_eq!((sqrt2 / (sqrt2 + sqrt2)).to_string(), "1/2");
+        assert_eq!(Real::from(Surd::from_rational(r(9, 4))).sqrt().to_string(), "3/2");
+        assert!(!(sqrt2 + sqrt3).is_exact());
+        assert!(((sqrt2 * sqrt3).to_f64() - 6f64.sqrt()).abs() < 1e-12);
+        assert!(!sqrt2.sqrt().is_exact());
+        assert_eq!((-sqrt2).to_string(), "-sqrt(2)");
+    }
+
+    #[test]
+    fn to_string() {
+        assert_eq!(Surd::new(r(1, 2), r(-3, 4), 5).unwrap().to_string(), "1/2 - 3/4*sqrt(5)");
+        assert_eq!(Surd::sqrt(r(5, 1)).unwrap().to_string(), "sqrt(5)");
+        assert_eq!(Surd::from_rational(r(-2, 3)).to_string(), "-2/3");
+    }
+}
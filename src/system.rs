@@ -0,0 +1,128 @@
+// Systems of equations in x and y, like the two curves of an
+// intersection exercise.
+//
+// A system is written either as a block { eq1; eq2 } or as one
+// equation per line. Every equation has to be a conic, that is a
+// polynomial equation of degree at most 2 in x and y.
+
+use crate::expression::Equation;
+use crate::conic::Conic;
+use crate::lang::EquationParser;
+use crate::lang::SystemParser;
+use crate::polynomial::Polynomial;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct System {
+    equations: Vec<Equation>,
+    conics: Vec<Conic>
+}
+
+// The index is the position of the offending equation in the system.
+#[derive(Debug, PartialEq)]
+pub enum SystemError {
+    Parse(String),
+    NotPolynomial(usize),
+    UnknownVariable(usize, String),
+    DegreeTooHigh(usize, u32)
+}
+
+impl System {
+    pub fn new(equations: Vec<Equation>) -> Result<System, SystemError> {
+        let mut conics: Vec<Conic> = Vec::new();
+        for (i, e) in equations.iter().enumerate() {
+            let p = Polynomial::from_expression(&e.residual()).ok_or(SystemError::NotPolynomial(i))?;
+            if let Some(v) = p.variables().into_iter().find(|v| v != "x" && v != "y") {
+                return Err(SystemError::UnknownVariable(i, v));
+            }
+            if p.degree() > 2 {
+                return Err(SystemError::DegreeTooHigh(i, p.degree()));
+            }
+            conics.push(Conic::from_polynomial(&p).unwrap());
+        }
+        Ok(System{equations, conics})
+    }
+
+    pub fn equations(&self) -> &[Equation] {
+        &self.equations
+    }
+
+    // The equations as conics, in the same order.
+    pub fn conics(&self) -> &[Conic] {
+        &self.conics
+    }
+
+    pub fn len(&self) -> usize {
+        self.equations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.equations.is_empty()
+    }
+}
+
+// A block in braces is parsed by the grammar. Otherwise every line
+// that is not blank is one equation, and parse errors name the line.
+pub fn parse_system(input: &str) -> Result<System, SystemError> {
+    if input.trim_start().starts_with('{') {
+        let equations = SystemParser::new().parse(input).map_err(|e| SystemError::Parse(e.to_string()))?;
+        return System::new(equations);
+    }
+    let mut equations: Vec<Equation> = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let e = EquationParser::new().parse(line)
+            .map_err(|e| SystemError::Parse(format!("line {}: {}", number + 1, e)))?;
+        equations.push(e);
+    }
+    System::new(equations)
+}
+
+impl ToString for SystemError {
+    fn to_string(&self) -> String {
+        match self {
+            SystemError::Parse(message) => message.clone(),
+            SystemError::NotPolynomial(i) => format!("equation {} is not polynomial", i + 1),
+            SystemError::UnknownVariable(i, v) => format!("equation {} uses {}, only x and y are allowed", i + 1, v),
+            SystemError::DegreeTooHigh(i, degree) => format!("equation {} has degree {}, at most 2 is allowed", i + 1, degree)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::num_rational::Rational32;
+    use super::*;
+
+    #[test]
+    fn block() {
+        let system = parse_system("{ x^2 + y^2 = 25; y = x + 1 }").unwrap();
+        assert_eq!(system.len(), 2);
+        assert_eq!(system.equations()[1].to_string(), "y = (x + 1)");
+        assert_eq!(system.conics()[0].f, Rational32::from(-25));
+        assert_eq!(parse_system("{ x = 1; }").unwrap().len(), 1);
+        assert!(matches!(parse_system("{ }"), Err(SystemError::Parse(_))));
+        assert!(matches!(parse_system("{ x = 1 y = 2 }"), Err(SystemError::Parse(_))));
+    }
+
+    #[test]
+    fn lines() {
+        let system = parse_system("x^2/4 + y^2 = 1\n\n  x*y = 1\n").unwrap();
+        assert_eq!(system.len(), 2);
+        assert_eq!(system.conics()[1].b, Rational32::from(1));
+        match parse_system("x = 1\ny = \n") {
+            Err(SystemError::Parse(message)) => assert!(message.starts_with("line 2: ")),
+            other => panic!("Expected a parse error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(parse_system("{ x = 1; y^3 = x }"), Err(SystemError::DegreeTooHigh(1, 3)));
+        assert_eq!(parse_system("{ x^2*y = 1 }"), Err(SystemError::DegreeTooHigh(0, 3)));
+        assert_eq!(parse_system("x + z = 1"), Err(SystemError::UnknownVariable(0, String::from("z"))));
+        assert_eq!(parse_system("y = 1/x"), Err(SystemError::NotPolynomial(0)));
+        assert_eq!(parse_system("y = sqrt(x)").unwrap_err().to_string(), "equation 1 is not polynomial");
+    }
+}
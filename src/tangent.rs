@@ -0,0 +1,208 @@
+// Tangent lines to conics.
+//
+// The line through P with direction v meets the conic where
+// alpha t^2 + beta t + gamma = 0, with the coefficients from
+// intersection::restrict. It is tangent when the two intersections
+// coincide, so when alpha is not zero and beta^2 - 4 alpha gamma = 0.
+// For the lines through a fixed point, or with a fixed direction, this
+// is a quadratic equation in the slope or in the offset of the line,
+// solved exactly with the quadratic solver.
+
+use std::cmp::Ordering;
+use ::num_rational::Rational32;
+use crate::conic::Conic;
+use crate::intersection::restrict;
+use crate::line::Line;
+use crate::quadratic::solve_quadratic;
+use crate::surd::Surd;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tangents {
+    // The point lies on the conic, which has this tangent there.
+    AtPoint(Line),
+    // The point is not on the conic. There are two tangents from a point
+    // outside, none from a point inside and one from some points of
+    // degenerate conics.
+    Through(Vec<Line>),
+    // The point is a singular point of a degenerate conic, like the
+    // crossing of two lines, where there is no single tangent.
+    Singular
+}
+
+// The tangents through the point. Lines in an asymptotic direction that
+// only touch the conic at infinity, like the asymptotes of a hyperbola
+// through its center, are not tangents. None on overflow and when the
+// slopes are not quadratic surds.
+pub fn tangents_through(conic: &Conic, point: (Surd, Surd)) -> Option<Tangents> {
+    let horizontal = restrict(conic, point, (Surd::one(), Surd::zero()))?;
+    let vertical = restrict(conic, point, (Surd::zero(), Surd::one()))?;
+    let gamma = horizontal[2];
+    if gamma.is_zero() {
+        // The gradient of the equation at the point is (beta_h, beta_v).
+        if horizontal[1].is_zero() && vertical[1].is_zero() {
+            return Some(Tangents::Singular);
+        }
+        let half = Surd::from_rational(Rational32::new(1, 2));
+        let normal = (horizontal[1].checked_mul(&half)?, vertical[1].checked_mul(&half)?);
+        let line = Line::through(point, (normal.1.checked_neg()?, normal.0))?;
+        return Some(Tangents::AtPoint(positive(line)?));
+    }
+    // alpha(m) and beta(m) for the direction (1, m) are quadratic and
+    // linear in m, and follow from the directions with m = 0, 1, -1.
+    let rising = restrict(conic, point, (Surd::one(), Surd::one()))?;
+    let falling = restrict(conic, point, (Surd::one(), Surd::one().checked_neg()?))?;
+    let (alpha, beta) = interpolate(&horizontal, &rising, &falling)?;
+    // (beta0 + beta1 m)^2 - 4 gamma (alpha0 + alpha1 m + alpha2 m^2) = 0
+    let four_gamma = gamma.checked_mul(&Surd::from_rational(Rational32::from(4)))?;
+    let slopes = solve_quadratic(
+        &beta[1].checked_mul(&beta[1])?.checked_sub(&four_gamma.checked_mul(&alpha[2])?)?,
+        &beta[0].checked_mul(&beta[1])?.checked_add(&beta[0].checked_mul(&beta[1])?)?
+            .checked_sub(&four_gamma.checked_mul(&alpha[1])?)?,
+        &beta[0].checked_mul(&beta[0])?.checked_sub(&four_gamma.checked_mul(&alpha[0])?)?)?;
+    let mut lines = Vec::new();
+    for m in slopes {
+        let alpha_m = alpha[0].checked_add(&alpha[1].checked_mul(&m)?)?.checked_add(&alpha[2].checked_mul(&m)?.checked_mul(&m)?)?;
+        if !alpha_m.is_zero() {
+            lines.push(positive(Line::through(point, (Surd::one(), m))?)?);
+        }
+    }
+    let [alpha_v, beta_v, _] = vertical;
+    if !alpha_v.is_zero() && beta_v.checked_mul(&beta_v)? == four_gamma.checked_mul(&alpha_v)? {
+        lines.push(positive(Line::through(point, (Surd::zero(), Surd::one()))?)?);
+    }
+    Some(Tangents::Through(lines))
+}
+
+// The tangents y = m x + k with the given slope m, in increasing order
+// of k. None on overflow, when the offsets are not quadratic surds and
+// when every line with the slope is tangent, which happens for a double
+// line.
+pub fn tangents_with_slope(conic: &Conic, slope: Surd) -> Option<Vec<Line>> {
+    tangents_with_direction(conic, (Surd::one(), slope))
+}
+
+// The tangents parallel to the direction (vx, vy). They pass through
+// the points s (-vy, vx), where alpha is fixed and beta and gamma are
+// linear and quadratic in s, which follow from s = 0, 1, -1.
+pub fn tangents_with_direction(conic: &Conic, direction: (Surd, Surd)) -> Option<Vec<Line>> {
+    let normal = (direction.1.checked_neg()?, direction.0);
+    let at = |s: Surd| -> Option<[Surd; 3]> {
+        restrict(conic, (s.checked_mul(&normal.0)?, s.checked_mul(&normal.1)?), direction)
+    };
+    let origin = at(Surd::zero())?;
+    let alpha = origin[0];
+    if alpha.is_zero() {
+        return Some(Vec::new());
+    }
+    let forward = at(Surd::one())?;
+    let backward = at(Surd::one().checked_neg()?)?;
+    let half = Surd::from_rational(Rational32::new(1, 2));
+    let beta = [origin[1], forward[1].checked_sub(&backward[1])?.checked_mul(&half)?];
+    let gamma = [
+        origin[2],
+        forward[2].checked_sub(&backward[2])?.checked_mul(&half)?,
+        forward[2].checked_add(&backward[2])?.checked_mul(&half)?.checked_sub(&origin[2])?
+    ];
+    // (beta0 + beta1 s)^2 - 4 alpha (gamma0 + gamma1 s + gamma2 s^2) = 0
+    let four_alpha = alpha.checked_mul(&Surd::from_rational(Rational32::from(4)))?;
+    let a = beta[1].checked_mul(&beta[1])?.checked_sub(&four_alpha.checked_mul(&gamma[2])?)?;
+    let b = beta[0].checked_mul(&beta[1])?.checked_add(&beta[0].checked_mul(&beta[1])?)?
+        .checked_sub(&four_alpha.checked_mul(&gamma[1])?)?;
+    let c = beta[0].checked_mul(&beta[0])?.checked_sub(&four_alpha.checked_mul(&gamma[0])?)?;
+    if a.is_zero() && b.is_zero() && c.is_zero() {
+        return None;
+    }
+    let mut lines = Vec::new();
+    for s in solve_quadratic(&a, &b, &c)? {
+        let point = (s.checked_mul(&normal.0)?, s.checked_mul(&normal.1)?);
+        lines.push(positive(Line::through(point, direction)?)?);
+    }
+    Some(lines)
+}
+
+// The coefficients of alpha(m) = alpha0 + alpha1 m + alpha2 m^2 and
+// beta(m) = beta0 + beta1 m from their values at m = 0, 1, -1.
+fn interpolate(zero: &[Surd; 3], one: &[Surd; 3], minus_one: &[Surd; 3]) -> Option<([Surd; 3], [Surd; 2])> {
+    let half = Surd::from_rational(Rational32::new(1, 2));
+    let alpha = [
+        zero[0],
+        one[0].checked_sub(&minus_one[0])?.checked_mul(&half)?,
+        one[0].checked_add(&minus_one[0])?.checked_mul(&half)?.checked_sub(&zero[0])?
+    ];
+    let beta = [zero[1], one[1].checked_sub(&minus_one[1])?.checked_mul(&half)?];
+    Some((alpha, beta))
+}
+
+// The same line with a positive first coefficient.
+fn positive(line: Line) -> Option<Line> {
+    let leading = if line.a.is_zero() { line.b } else { line.a };
+    if leading.signum() == Ordering::Less {
+        return Some(Line::new(line.a.checked_neg()?, line.b.checked_neg()?, line.c.checked_neg()?));
+    }
+    Some(line)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conic::parse_conic;
+
+    fn r(n: i32) -> Surd {
+        Surd::from_rational(Rational32::from(n))
+    }
+
+    fn through(input: &str, point: (i32, i32)) -> Vec<String> {
+        match tangents_through(&parse_conic(input), (r(point.0), r(point.1))).unwrap() {
+            Tangents::Through(lines) => lines.iter().map(Line::to_string).collect(),
+            other => panic!("Expected tangents through the point, got {:?}", other)
+        }
+    }
+
+    fn with_slope(input: &str, slope: i32) -> Vec<String> {
+        tangents_with_slope(&parse_conic(input), r(slope)).unwrap().iter().map(Line::to_string).collect()
+    }
+
+    #[test]
+    fn at_point() {
+        let tangent = tangents_through(&parse_conic("x^2 + y^2 = 25"), (r(3), r(-4))).unwrap();
+        assert_eq!(tangent, Tangents::AtPoint(Line::from_rational(Rational32::from(3), Rational32::from(-4), Rational32::from(-25))));
+        let tangent = tangents_through(&parse_conic("y = x^2"), (r(1), r(1))).unwrap();
+        assert_eq!(tangent, Tangents::AtPoint(Line::from_rational(Rational32::from(1), Rational32::new(-1, 2), Rational32::new(-1, 2))));
+    }
+
+    #[test]
+    fn from_outside() {
+        assert_eq!(through("x^2 + y^2 = 25", (5, 5)), vec!["y - 5 = 0", "x - 5 = 0"]);
+        assert_eq!(through("x^2 + y^2 = 1", (2, 0)), vec![
+            "1/3*sqrt(3)*x + y - 2/3*sqrt(3) = 0",
+            "1/3*sqrt(3)*x - y - 2/3*sqrt(3) = 0"]);
+        // The tangents of y = x^2 at x = -1 and x = 3 meet at (1, -3).
+        assert_eq!(through("y = x^2", (1, -3)), vec!["2*x + y + 1 = 0", "6*x - y - 9 = 0"]);
+    }
+
+    #[test]
+    fn from_inside() {
+        assert_eq!(through("x^2 + y^2 = 25", (1, 1)), Vec::<String>::new());
+        assert_eq!(through("y = x^2", (0, 1)), Vec::<String>::new());
+        // The asymptotes only touch at infinity.
+        assert_eq!(through("x^2 - y^2 = 1", (0, 0)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn singular_point() {
+        assert_eq!(tangents_through(&parse_conic("x^2 - y^2 = 0"), (r(0), r(0))), Some(Tangents::Singular));
+    }
+
+    #[test]
+    fn given_slope() {
+        assert_eq!(with_slope("y = x^2", 2), vec!["2*x - y - 1 = 0"]);
+        assert_eq!(with_slope("x^2 + y^2 = 2", 1), vec!["x - y - 2 = 0", "x - y + 2 = 0"]);
+        assert_eq!(with_slope("x*y = 1", -1), vec!["x + y + 2 = 0", "x + y - 2 = 0"]);
+        assert_eq!(with_slope("x*y = 1", 1), Vec::<String>::new());
+        assert_eq!(with_slope("x^2 + y^2 = 1", 1), vec!["x - y - sqrt(2) = 0", "x - y + sqrt(2) = 0"]);
+        // Lines parallel to the axis of a parabola meet it once.
+        assert_eq!(tangents_with_direction(&parse_conic("y = x^2"), (r(0), r(1))), Some(Vec::new()));
+        assert_eq!(tangents_with_direction(&parse_conic("x^2 + y^2 = 4"), (r(0), r(1))).unwrap().iter().map(Line::to_string).collect::<Vec<_>>(),
+            vec!["x - 2 = 0", "x + 2 = 0"]);
+    }
+}
@@ -0,0 +1,184 @@
+// Affine and projective transformations of the plane.
+//
+// A transformation is an invertible 3x3 matrix T acting on homogeneous
+// coordinates, so the point (x, y) maps to (x'/w, y'/w) with
+// (x', y', w) = T (x, y, 1). Affine maps have the last row (0, 0, 1).
+// The image of the conic with matrix M consists of the points T p with
+// p^T M p = 0, so it is the conic with matrix T^-T M T^-1.
+
+use ::num_rational::Rational32;
+use num_traits::CheckedAdd;
+use num_traits::CheckedDiv;
+use num_traits::CheckedMul;
+use num_traits::One;
+use num_traits::Zero;
+use crate::conic::Conic;
+use crate::conic::Matrix3;
+use crate::linalg::Matrix;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Transform2D {
+    matrix: Matrix3
+}
+
+impl Transform2D {
+    // None for a singular matrix, which does not map the plane onto
+    // itself.
+    pub fn new(matrix: Matrix3) -> Option<Transform2D> {
+        if Matrix::from_matrix3(&matrix).determinant()?.is_zero() {
+            return None;
+        }
+        Some(Transform2D{matrix})
+    }
+
+    pub fn identity() -> Transform2D {
+        Transform2D::affine([[1, 0, 0], [0, 1, 0]].map(|row| row.map(Rational32::from)))
+    }
+
+    pub fn translation(dx: Rational32, dy: Rational32) -> Transform2D {
+        let (zero, one) = (Rational32::zero(), Rational32::one());
+        Transform2D::affine([[one, zero, dx], [zero, one, dy]])
+    }
+
+    // (x, y) to (sx x, sy y), None if a factor is zero.
+    pub fn scaling(sx: Rational32, sy: Rational32) -> Option<Transform2D> {
+        let zero = Rational32::zero();
+        Transform2D::new(with_last_row([[sx, zero, zero], [zero, sy, zero]]))
+    }
+
+    // (x, y) to (x + kx y, ky x + y), None if kx ky = 1.
+    pub fn shear(kx: Rational32, ky: Rational32) -> Option<Transform2D> {
+        let (zero, one) = (Rational32::zero(), Rational32::one());
+        Transform2D::new(with_last_row([[one, kx, zero], [ky, one, zero]]))
+    }
+
+    // The rotation about the origin with cos = a/c and sin = b/c for a
+    // Pythagorean triple a^2 + b^2 = c^2, like 3, 4, 5. These are the
+    // rotations that map rational points to rational points. None if
+    // the numbers are not a Pythagorean triple.
+    pub fn rotation(a: i32, b: i32, c: i32) -> Option<Transform2D> {
+        let square = |n: i32| i64::from(n) * i64::from(n);
+        if c == 0 || square(a) + square(b) != square(c) {
+            return None;
+        }
+        let (cos, sin) = (Rational32::new(a, c), Rational32::new(b, c));
+        let zero = Rational32::zero();
+        Transform2D::new(with_last_row([[cos, -sin, zero], [sin, cos, zero]]))
+    }
+
+    // The projective map with the matrix, None if it is singular.
+    pub fn projective(matrix: Matrix3) -> Option<Transform2D> {
+        Transform2D::new(matrix)
+    }
+
+    pub fn matrix(&self) -> Matrix3 {
+        self.matrix
+    }
+
+    pub fn is_affine(&self) -> bool {
+        let last = self.matrix[2];
+        last[0].is_zero() && last[1].is_zero() && !last[2].is_zero()
+    }
+
+    // This transformation followed by the other one.
+    pub fn then(&self, other: &Transform2D) -> Option<Transform2D> {
+        let product = Matrix::from_matrix3(&other.matrix).checked_mul(&Matrix::from_matrix3(&self.matrix))?;
+        Transform2D::new(product.to_matrix3()?)
+    }
+
+    pub fn inverse(&self) -> Option<Transform2D> {
+        Transform2D::new(Matrix::from_matrix3(&self.matrix).inverse()?.to_matrix3()?)
+    }
+
+    // The image of the point, None on overflow and when the point is
+    // mapped to infinity.
+    pub fn apply(&self, point: (Rational32, Rational32)) -> Option<(Rational32, Rational32)> {
+        let p = [point.0, point.1, Rational32::one()];
+        let mut image = [Rational32::zero(); 3];
+        for (i, row) in self.matrix.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                image[i] = image[i].checked_add(&entry.checked_mul(&p[j])?)?;
+            }
+        }
+        if image[2].is_zero() {
+            return None;
+        }
+        Some((image[0].checked_div(&image[2])?, image[1].checked_div(&image[2])?))
+    }
+
+    // The image of the conic with matrix T^-T M T^-1. None on overflow.
+    pub fn map_conic(&self, conic: &Conic) -> Option<Conic> {
+        let inverse = Matrix::from_matrix3(&self.matrix).inverse()?;
+        let m = inverse.transpose().checked_mul(&Matrix::from_matrix3(&conic.matrix()?))?.checked_mul(&inverse)?;
+        Conic::from_matrix(&m.to_matrix3()?)
+    }
+
+    fn affine(rows: [[Rational32; 3]; 2]) -> Transform2D {
+        Transform2D{matrix: with_last_row(rows)}
+    }
+}
+
+fn with_last_row(rows: [[Rational32; 3]; 2]) -> Matrix3 {
+    [rows[0], rows[1], [Rational32::zero(), Rational32::zero(), Rational32::one()]]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conic::ConicKind;
+    use crate::conic::parse_conic;
+
+    fn r(n: i32) -> Rational32 {
+        Rational32::from(n)
+    }
+
+    #[test]
+    fn affine_maps() {
+        let circle = parse_conic("x^2 + y^2 = 25");
+        let moved = Transform2D::translation(r(1), r(2)).map_conic(&circle).unwrap();
+        assert_eq!(moved.to_string(), "x^2 + y^2 - 2*x - 4*y - 20 = 0");
+        let ellipse = Transform2D::scaling(r(2), r(3)).unwrap().map_conic(&parse_conic("x^2 + y^2 = 1")).unwrap();
+        assert_eq!(ellipse.to_string(), "1/4*x^2 + 1/9*y^2 - 1 = 0");
+        let sheared = Transform2D::shear(r(1), r(0)).unwrap();
+        assert_eq!(sheared.apply((r(1), r(2))), Some((r(3), r(2))));
+        assert_eq!(sheared.map_conic(&parse_conic("y = x^2")).unwrap().to_string(), "-x^2 + 2*x*y - y^2 + y = 0");
+        assert_eq!(Transform2D::scaling(r(0), r(1)), None);
+        assert_eq!(Transform2D::shear(r(2), Rational32::new(1, 2)), None);
+    }
+
+    #[test]
+    fn rotation() {
+        let rotation = Transform2D::rotation(3, 4, 5).unwrap();
+        assert_eq!(rotation.apply((r(1), r(0))), Some((Rational32::new(3, 5), Rational32::new(4, 5))));
+        let hyperbola = rotation.map_conic(&parse_conic("x^2 - y^2 = 1")).unwrap();
+        assert_eq!(hyperbola.to_string(), "-7/25*x^2 + 48/25*x*y + 7/25*y^2 - 1 = 0");
+        assert_eq!(hyperbola.kind(), Some(ConicKind::Hyperbola));
+        assert_eq!(Transform2D::rotation(1, 2, 3), None);
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let map = Transform2D::translation(r(1), r(-1)).then(&Transform2D::rotation(0, 1, 1).unwrap()).unwrap();
+        assert_eq!(map.apply((r(1), r(1))), Some((r(0), r(2))));
+        assert!(map.is_affine());
+        let ellipse = parse_conic("x^2 + x*y + 2*y^2 = 7");
+        let image = map.map_conic(&ellipse).unwrap();
+        assert_eq!(map.inverse().unwrap().map_conic(&image), Some(ellipse));
+        assert_eq!(map.then(&map.inverse().unwrap()), Some(Transform2D::identity()));
+    }
+
+    #[test]
+    fn projective_map() {
+        // (x, y) to (x, y)/(x + 1) sends the tangent x = -1 of the unit
+        // circle to infinity, which makes the circle a parabola.
+        let one = r(1);
+        let zero = r(0);
+        let map = Transform2D::projective([[one, zero, zero], [zero, one, zero], [one, zero, one]]).unwrap();
+        assert!(!map.is_affine());
+        let parabola = map.map_conic(&parse_conic("x^2 + y^2 = 1")).unwrap();
+        assert_eq!(parabola.kind(), Some(ConicKind::Parabola));
+        assert_eq!(map.apply((r(0), r(1))), Some((r(0), r(1))));
+        assert_eq!(map.apply((r(-1), r(0))), None);
+        assert_eq!(Transform2D::projective([[one, zero, zero], [one, zero, zero], [zero, zero, one]]), None);
+    }
+}
@@ -0,0 +1,233 @@
+// Traversals of the Expression tree.
+//
+// A Visitor inspects a tree, a VisitorMut changes it in place and a
+// Fold computes a value bottom-up from the values of the children.
+// Every method of Visitor and VisitorMut has a default that just walks
+// into the children, so a pass only overrides the nodes it is
+// interested in. The walk_* functions are the default behaviour and
+// can be called from an overridden method to continue the walk.
+
+use crate::expression::Expression;
+use crate::expression::Composite;
+use crate::expression::Call;
+use crate::expression::Operator;
+
+pub trait Visitor {
+    fn visit_expression(&mut self, e: &Expression) {
+        walk_expression(self, e);
+    }
+
+    fn visit_number(&mut self, _number: &str) {
+    }
+
+    fn visit_variable(&mut self, _name: &str) {
+    }
+
+    fn visit_negation(&mut self, inner: &Expression) {
+        self.visit_expression(inner);
+    }
+
+    fn visit_composite(&mut self, c: &Composite) {
+        walk_composite(self, c);
+    }
+
+    fn visit_call(&mut self, c: &Call) {
+        walk_call(self, c);
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, e: &Expression) {
+    match e {
+        Expression::Number(n) => visitor.visit_number(n),
+        Expression::Variable(v) => visitor.visit_variable(v),
+        Expression::Negation(inner) => visitor.visit_negation(inner),
+        Expression::Composite(c) => visitor.visit_composite(c),
+        Expression::Call(c) => visitor.visit_call(c)
+    }
+}
+
+pub fn walk_composite<V: Visitor + ?Sized>(visitor: &mut V, c: &Composite) {
+    visitor.visit_expression(&c.left);
+    visitor.visit_expression(&c.right);
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(visitor: &mut V, c: &Call) {
+    for argument in c.arguments.iter() {
+        visitor.visit_expression(argument);
+    }
+}
+
+pub trait VisitorMut {
+    fn visit_expression_mut(&mut self, e: &mut Expression) {
+        walk_expression_mut(self, e);
+    }
+
+    fn visit_number_mut(&mut self, _number: &mut String) {
+    }
+
+    fn visit_variable_mut(&mut self, _name: &mut String) {
+    }
+
+    fn visit_negation_mut(&mut self, inner: &mut Expression) {
+        self.visit_expression_mut(inner);
+    }
+
+    fn visit_composite_mut(&mut self, c: &mut Composite) {
+        walk_composite_mut(self, c);
+    }
+
+    fn visit_call_mut(&mut self, c: &mut Call) {
+        walk_call_mut(self, c);
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, e: &mut Expression) {
+    match e {
+        Expression::Number(n) => visitor.visit_number_mut(n),
+        Expression::Variable(v) => visitor.visit_variable_mut(v),
+        Expression::Negation(inner) => visitor.visit_negation_mut(inner),
+        Expression::Composite(c) => visitor.visit_composite_mut(c),
+        Expression::Call(c) => visitor.visit_call_mut(c)
+    }
+}
+
+pub fn walk_composite_mut<V: VisitorMut + ?Sized>(visitor: &mut V, c: &mut Composite) {
+    visitor.visit_expression_mut(&mut c.left);
+    visitor.visit_expression_mut(&mut c.right);
+}
+
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(visitor: &mut V, c: &mut Call) {
+    for argument in c.arguments.iter_mut() {
+        visitor.visit_expression_mut(argument);
+    }
+}
+
+pub trait Fold {
+    type Output;
+
+    fn fold_number(&mut self, number: &str) -> Self::Output;
+    fn fold_variable(&mut self, name: &str) -> Self::Output;
+    fn fold_negation(&mut self, inner: Self::Output) -> Self::Output;
+    fn fold_composite(&mut self, operator: &Operator, left: Self::Output, right: Self::Output) -> Self::Output;
+    fn fold_call(&mut self, name: &str, arguments: Vec<Self::Output>) -> Self::Output;
+}
+
+pub fn fold<F: Fold + ?Sized>(e: &Expression, folder: &mut F) -> F::Output {
+    match e {
+        Expression::Number(n) => folder.fold_number(n),
+        Expression::Variable(v) => folder.fold_variable(v),
+        Expression::Negation(inner) => {
+            let inner = fold(inner, folder);
+            folder.fold_negation(inner)
+        },
+        Expression::Composite(c) => {
+            let left = fold(&c.left, folder);
+            let right = fold(&c.right, folder);
+            folder.fold_composite(&c.operator, left, right)
+        },
+        Expression::Call(c) => fold_call(c, folder)
+    }
+}
+
+// Kept out of fold, whose stack frame bounds the depth of the trees
+// that can be folded.
+#[inline(never)]
+fn fold_call<F: Fold + ?Sized>(c: &Call, folder: &mut F) -> F::Output {
+    let arguments = c.arguments.iter().map(|argument| fold(argument, folder)).collect();
+    folder.fold_call(&c.name, arguments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ExprParser;
+
+    struct CountNodes {
+        count: u32
+    }
+
+    impl Fold for CountNodes {
+        type Output = u32;
+
+        fn fold_number(&mut self, _number: &str) -> u32 {
+            1
+        }
+
+        fn fold_variable(&mut self, _name: &str) -> u32 {
+            1
+        }
+
+        fn fold_negation(&mut self, inner: u32) -> u32 {
+            self.count += 1;
+            inner + 1
+        }
+
+        fn fold_composite(&mut self, _operator: &Operator, left: u32, right: u32) -> u32 {
+            self.count += 1;
+            left + right + 1
+        }
+
+        fn fold_call(&mut self, _name: &str, arguments: Vec<u32>) -> u32 {
+            self.count += 1;
+            arguments.iter().sum::<u32>() + 1
+        }
+    }
+
+    struct CollectNumbers {
+        numbers: Vec<String>
+    }
+
+    impl Visitor for CollectNumbers {
+        fn visit_number(&mut self, number: &str) {
+            self.numbers.push(number.to_owned());
+        }
+
+        // Do not look into exponents
+        fn visit_composite(&mut self, c: &Composite) {
+            if matches!(c.operator, Operator::POW) {
+                self.visit_expression(&c.left);
+            } else {
+                walk_composite(self, c);
+            }
+        }
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_variable_mut(&mut self, name: &mut String) {
+            name.push('1');
+        }
+    }
+
+    #[test]
+    fn fold_counts_nodes() {
+        let e = ExprParser::new().parse("-x^2 + 3*y").unwrap();
+        let mut counter = CountNodes{count: 0};
+        assert_eq!(fold(&e, &mut counter), 8);
+        assert_eq!(counter.count, 4);
+    }
+
+    #[test]
+    fn fold_into_calls() {
+        let e = ExprParser::new().parse("abs(x - 1) + f(x, 2*y)").unwrap();
+        let mut counter = CountNodes{count: 0};
+        assert_eq!(fold(&e, &mut counter), 10);
+        assert_eq!(counter.count, 5);
+    }
+
+    #[test]
+    fn visitor_overrides_walk() {
+        let e = ExprParser::new().parse("2*x^3 - (4 + y)^5").unwrap();
+        let mut collector = CollectNumbers{numbers: Vec::new()};
+        collector.visit_expression(&e);
+        assert_eq!(collector.numbers, vec!["2", "4"]);
+    }
+
+    #[test]
+    fn visitor_mut_changes_tree() {
+        let mut e = *ExprParser::new().parse("x*y - -x").unwrap();
+        Rename.visit_expression_mut(&mut e);
+        assert_eq!(e.to_string(), "((x1 * y1) - (-x1))");
+    }
+}